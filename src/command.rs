@@ -1,15 +1,26 @@
 // Copyright 2022 Peter Williams <pwil3058@gmail.com> <pwil3058@bigpond.net.au>
 
-use crate::command_action::CommandAction;
+use crate::command_action::{CommandAction, Input, Output};
 use crate::error::Error;
+use crate::expansion::{expand, expand_fields, expand_here_doc, remove_quotes};
 use crate::script::EnvVars;
 use lalr1::Parser;
 use std::convert::From;
 use std::env;
+use std::fs::{self, File, OpenOptions, Permissions};
+use std::io::{PipeReader, Read, Write};
+use std::os::fd::OwnedFd;
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::process::ExitStatusExt;
+use std::path::Path;
+use std::process::{Child, Stdio};
+use std::thread::{self, JoinHandle};
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Outcome {
     pub e_code: Option<i32>,
+    // The signal that killed the (last) program
+    pub signal: Option<i32>,
     pub std_out: String,
     pub std_err: String,
 }
@@ -18,108 +29,608 @@ impl Default for Outcome {
     fn default() -> Self {
         Self {
             e_code: Some(0),
+            signal: None,
             std_out: String::new(),
             std_err: String::new(),
         }
     }
 }
 
-impl From<std::process::Output> for Outcome {
-    fn from(output: std::process::Output) -> Self {
-        Outcome {
-            e_code: output.status.code(),
-            std_out: String::from_utf8(output.stdout).unwrap(),
-            std_err: String::from_utf8(output.stderr).unwrap(),
+impl Outcome {
+    // The combined outcome of running this command and then `next`.
+    fn followed_by(mut self, next: Outcome) -> Self {
+        self.e_code = next.e_code;
+        self.signal = next.signal;
+        self.std_out.push_str(&next.std_out);
+        self.std_err.push_str(&next.std_err);
+        self
+    }
+}
+
+fn open_output(path: &str, overwrite: bool) -> Result<File, Error> {
+    if overwrite {
+        Ok(File::create(path)?)
+    } else {
+        Ok(OpenOptions::new().append(true).create(true).open(path)?)
+    }
+}
+
+// Build the process for a RunProgram with its arguments and redirection
+// paths expanded along with the data (if any) to be written to its stdin
+// and the readers for its (unredirected) stdout and stderr. Piped input
+// is used if there is no input redirection.
+fn program_command(
+    program: &CommandAction,
+    piped_input: Option<Stdio>,
+    env_vars: &EnvVars,
+) -> Result<
+    (
+        std::process::Command,
+        Option<String>,
+        PipeReader,
+        PipeReader,
+    ),
+    Error,
+> {
+    let (assignments, mut fields, input, output, err_output) = match program {
+        CommandAction::RunProgram(assignments, program_name, args, input, output, err_output) => {
+            let mut fields = expand_fields(program_name, env_vars)?;
+            for arg in args.iter() {
+                fields.extend(expand_fields(arg, env_vars)?);
+            }
+            if fields.is_empty() {
+                return Err(Error::Why("Program name expanded to nothing"));
+            }
+            (assignments.as_slice(), fields, input, output, err_output)
+        }
+        CommandAction::Shell(cmd_line, here_docs) => {
+            // the here-documents follow the command line as they would in a shell script
+            let mut script = cmd_line.clone();
+            for (delimiter, body) in here_docs {
+                script.push_str(&format!("\n{body}{delimiter}"));
+            }
+            let fields = vec!["sh".to_string(), "-c".to_string(), script];
+            (&[][..], fields, &None, &None, &None)
+        }
+        _ => return Err(Error::Why("Pipeline stages must be programs")),
+    };
+    let mut input_data = None;
+    let input_file = match input {
+        Some(Input::File(path)) => Stdio::from(File::open(expand(path, env_vars)?)?),
+        Some(Input::HereDoc(delimiter, body)) => {
+            if delimiter.contains(['\'', '"', '\\']) {
+                input_data = Some(body.clone());
+            } else {
+                input_data = Some(expand_here_doc(body, env_vars)?);
+            }
+            Stdio::piped()
+        }
+        Some(Input::HereString(word)) => {
+            input_data = Some(expand(word, env_vars)? + "\n");
+            Stdio::piped()
+        }
+        None => piped_input.unwrap_or_else(Stdio::null),
+    };
+    // As in a shell, stdout is redirected before stderr so "2>&1" sends
+    // stderr wherever stdout was redirected to. Sharing the one pipe
+    // preserves the order in which the program wrote to the two streams.
+    let (out_reader, out_writer) = std::io::pipe()?;
+    let (err_reader, err_writer) = std::io::pipe()?;
+    let output_fd: OwnedFd = match output {
+        Some(Output::File(path, overwrite)) => {
+            open_output(&expand(path, env_vars)?, *overwrite)?.into()
+        }
+        Some(Output::StdErr) => err_writer.try_clone()?.into(),
+        _ => out_writer.into(),
+    };
+    let err_output_fd: OwnedFd = match err_output {
+        Some(Output::File(path, overwrite)) => {
+            open_output(&expand(path, env_vars)?, *overwrite)?.into()
+        }
+        Some(Output::StdOut) => output_fd.try_clone()?,
+        _ => err_writer.into(),
+    };
+    let mut command = std::process::Command::new(fields.remove(0));
+    command
+        .args(fields)
+        .stdin(input_file)
+        .stdout(output_fd)
+        .stderr(err_output_fd)
+        .envs(env_vars.vars());
+    if !assignments.is_empty() {
+        // as in a shell, each assignment can use those before it
+        let mut overlay = env_vars.clone();
+        for (name, value) in assignments.iter() {
+            if env_vars.is_special(name) {
+                return Err(Error::Why("Special variables are read-only"));
+            }
+            let value = expand(value, &overlay)?;
+            overlay.set_var(name, &value);
+            command.env(name, value);
+        }
+    }
+    Ok((command, input_data, out_reader, err_reader))
+}
+
+// A pipeline that has been started along with the threads feeding its
+// input and collecting its output.
+#[derive(Debug)]
+pub struct Job {
+    children: Vec<Child>,
+    in_writers: Vec<JoinHandle<Option<()>>>,
+    out_reader: JoinHandle<std::io::Result<String>>,
+    err_readers: Vec<JoinHandle<std::io::Result<String>>>,
+}
+
+fn read_pipe(mut pipe: PipeReader) -> JoinHandle<std::io::Result<String>> {
+    thread::spawn(move || {
+        let mut text = String::new();
+        pipe.read_to_string(&mut text)?;
+        Ok(text)
+    })
+}
+
+impl Job {
+    // The process id of the last program in the pipeline
+    pub fn pid(&self) -> u32 {
+        self.children.last().map(Child::id).unwrap_or_default()
+    }
+
+    // Wait for the pipeline to finish. The exit code is that of the last
+    // stage and the stderr of every stage is collected (in stage order).
+    fn wait(mut self) -> Result<Outcome, Error> {
+        let mut e_code = None;
+        let mut signal = None;
+        for child in self.children.iter_mut() {
+            let status = child.wait()?;
+            e_code = status.code();
+            signal = status.signal();
+        }
+        for in_writer in self.in_writers {
+            in_writer.join().ok();
+        }
+        let std_out = match self.out_reader.join() {
+            Ok(result) => result?,
+            Err(_) => return Err(Error::Why("Reading pipeline stdout failed")),
+        };
+        let mut std_err = String::new();
+        for err_reader in self.err_readers {
+            match err_reader.join() {
+                Ok(result) => std_err.push_str(&result?),
+                Err(_) => return Err(Error::Why("Reading pipeline stderr failed")),
+            }
+        }
+        Ok(Outcome {
+            e_code,
+            signal,
+            std_out,
+            std_err,
+        })
+    }
+
+    fn kill(&mut self) {
+        for child in self.children.iter_mut() {
+            child.kill().ok();
+            child.wait().ok();
+        }
+    }
+}
+
+// The background jobs that haven't been waited for. As in a shell, jobs
+// aren't inherited by subshells (so clones have no jobs) and any that are
+// still running when they are dropped are killed.
+#[derive(Debug, Default)]
+pub struct Jobs(pub Vec<Job>);
+
+impl Clone for Jobs {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl Drop for Jobs {
+    fn drop(&mut self) {
+        for job in self.0.iter_mut() {
+            job.kill();
+        }
+    }
+}
+
+// Start the stages with each one's stdout feeding the next one's stdin
+// (and the first one reading the command's input, if any).
+fn spawn_pipeline(
+    stages: &[CommandAction],
+    std_in: Option<&PipeReader>,
+    env_vars: &EnvVars,
+) -> Result<Job, Error> {
+    let mut children: Vec<Child> = vec![];
+    let mut in_writers = vec![];
+    let mut err_readers = vec![];
+    let mut previous_stdout: Option<PipeReader> = None;
+    for (index, stage) in stages.iter().enumerate() {
+        let piped_input = if index == 0 {
+            std_in
+                .map(|reader| reader.try_clone())
+                .transpose()?
+                .map(Stdio::from)
+        } else {
+            previous_stdout.take().map(Stdio::from)
+        };
+        let (mut command, input_data, stdout, stderr) =
+            program_command(stage, piped_input, env_vars)?;
+        let mut child = command.spawn().map_err(|err| {
+            Error::Spawn(command.get_program().to_string_lossy().to_string(), err)
+        })?;
+        // the pipes' write ends must be closed for their readers to see EOF
+        drop(command);
+        if let (Some(data), Some(mut stdin)) = (input_data, child.stdin.take()) {
+            // the program may not read all of its input so write errors are ignored
+            in_writers.push(thread::spawn(move || stdin.write_all(data.as_bytes()).ok()));
+        }
+        previous_stdout = Some(stdout);
+        err_readers.push(read_pipe(stderr));
+        children.push(child);
+    }
+    let out_reader = match previous_stdout {
+        Some(stdout) => read_pipe(stdout),
+        None => return Err(Error::Why("Empty pipeline")),
+    };
+    Ok(Job {
+        children,
+        in_writers,
+        out_reader,
+        err_readers,
+    })
+}
+
+const SIGNALS: [(&str, i32); 11] = [
+    ("HUP", libc::SIGHUP),
+    ("INT", libc::SIGINT),
+    ("QUIT", libc::SIGQUIT),
+    ("KILL", libc::SIGKILL),
+    ("USR1", libc::SIGUSR1),
+    ("USR2", libc::SIGUSR2),
+    ("PIPE", libc::SIGPIPE),
+    ("ALRM", libc::SIGALRM),
+    ("TERM", libc::SIGTERM),
+    ("CONT", libc::SIGCONT),
+    ("STOP", libc::SIGSTOP),
+];
+
+// The number of the signal given by name (with or without "SIG") or number
+pub fn signal_number(signal: &str) -> Result<i32, Error> {
+    if let Ok(number) = signal.parse::<i32>() {
+        return Ok(number);
+    }
+    let name = signal.strip_prefix("SIG").unwrap_or(signal);
+    SIGNALS
+        .iter()
+        .find(|(signal_name, _)| *signal_name == name)
+        .map(|(_, number)| *number)
+        .ok_or(Error::Why("Unknown signal"))
+}
+
+fn process_id(word: &str, env_vars: &EnvVars) -> Result<u32, Error> {
+    match expand(word, env_vars)?.parse::<u32>() {
+        Ok(pid) if pid > 0 => Ok(pid),
+        _ => Err(Error::Why("Malformed process id")),
+    }
+}
+
+// Change the current directory keeping PWD and OLDPWD up to date.
+fn change_dir(dir: &Path, env_vars: &mut EnvVars) -> Result<(), Error> {
+    let old_dir = env::current_dir()?;
+    env::set_current_dir(dir)?;
+    env_vars.set_var("OLDPWD", &old_dir.to_string_lossy());
+    env_vars.set_var("PWD", &env::current_dir()?.to_string_lossy());
+    Ok(())
+}
+
+// Run `cmd_action` with programs that have no other input reading from `std_in`
+// (which is shared, as a shell's stdin is, by all of the commands in a list)
+// and make its exit code (or 128 plus the number of the signal that killed
+// it) the value of "$?".
+fn run_action(
+    cmd_action: &CommandAction,
+    std_in: Option<&PipeReader>,
+    env_vars: &mut EnvVars,
+) -> Result<Outcome, Error> {
+    let outcome = perform_action(cmd_action, std_in, env_vars)?;
+    let e_code = match (outcome.e_code, outcome.signal) {
+        (Some(e_code), _) => e_code.to_string(),
+        (None, Some(signal)) => (128 + signal).to_string(),
+        (None, None) => String::new(),
+    };
+    env_vars.set_special("?", &e_code);
+    Ok(outcome)
+}
+
+// Run a command of a list. As in a shell, a command that can't be run (or a
+// built-in that fails) has a non-zero exit code and reports the error on
+// stderr rather than ending the script.
+fn run_list_member(
+    cmd_action: &CommandAction,
+    std_in: Option<&PipeReader>,
+    env_vars: &mut EnvVars,
+) -> Result<Outcome, Error> {
+    run_action(cmd_action, std_in, env_vars).or_else(|err| {
+        let e_code = match &err {
+            Error::Spawn(_, io_err) if io_err.kind() == std::io::ErrorKind::NotFound => 127,
+            Error::Spawn(_, _) => 126,
+            _ => 1,
+        };
+        env_vars.set_special("?", &e_code.to_string());
+        Ok(Outcome {
+            e_code: Some(e_code),
+            std_err: format!("{err}\n"),
+            ..Outcome::default()
+        })
+    })
+}
+
+fn perform_action(
+    cmd_action: &CommandAction,
+    std_in: Option<&PipeReader>,
+    env_vars: &mut EnvVars,
+) -> Result<Outcome, Error> {
+    use CommandAction::*;
+    match cmd_action {
+        SetEnvVar(var, _) | UnsetEnvVar(var) if env_vars.is_special(var) => {
+            Err(Error::Why("Special variables are read-only"))
+        }
+        SetEnvVar(var, value) => {
+            let value = expand(value, env_vars)?;
+            env_vars.set_var(var, &value);
+            Ok(Outcome::default())
+        }
+        UnsetEnvVar(var) => {
+            env_vars.remove_var(var);
+            Ok(Outcome::default())
+        }
+        ChangeDir(dir) => {
+            let dir = match dir.as_deref() {
+                None => env_vars
+                    .var("HOME")
+                    .ok_or(Error::Why("HOME not set"))?
+                    .clone(),
+                Some("-") => env_vars
+                    .var("OLDPWD")
+                    .ok_or(Error::Why("OLDPWD not set"))?
+                    .clone(),
+                Some(dir) => expand(dir, env_vars)?,
+            };
+            change_dir(Path::new(&dir), env_vars)?;
+            Ok(Outcome::default())
+        }
+        PushDir(dir) => {
+            let cwd = env::current_dir()?;
+            change_dir(Path::new(&expand(dir, env_vars)?), env_vars)?;
+            env_vars.push_dir(cwd);
+            Ok(Outcome::default())
+        }
+        PopDir => {
+            let dir = env_vars
+                .pop_dir()
+                .ok_or(Error::Why("Directory stack empty"))?;
+            change_dir(&dir, env_vars)?;
+            Ok(Outcome::default())
+        }
+        CreateFile(path, mode, _, contents) => {
+            let mode = mode
+                .as_ref()
+                .map(|mode| u32::from_str_radix(mode, 8))
+                .transpose()
+                .map_err(|_| Error::Why("Malformed file mode"))?;
+            let path = expand(path, env_vars)?;
+            let path = Path::new(&path);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(path, contents)?;
+            if let Some(mode) = mode {
+                fs::set_permissions(path, Permissions::from_mode(mode))?;
+            }
+            Ok(Outcome::default())
+        }
+        RunProgram(..) | Shell(..) => {
+            spawn_pipeline(std::slice::from_ref(cmd_action), std_in, env_vars)?.wait()
+        }
+        Pipeline(stages) => spawn_pipeline(stages, std_in, env_vars)?.wait(),
+        Background(stages) => {
+            // as in a shell, background jobs don't read the script's input
+            let job = spawn_pipeline(stages, None, env_vars)?;
+            env_vars.set_special("!", &job.pid().to_string());
+            env_vars.add_job(job);
+            Ok(Outcome::default())
+        }
+        Wait(None) => {
+            // as in a shell, the exit code is zero
+            let mut outcome = Outcome::default();
+            for job in env_vars.remove_jobs() {
+                let job_outcome = job.wait()?;
+                outcome.std_out.push_str(&job_outcome.std_out);
+                outcome.std_err.push_str(&job_outcome.std_err);
+            }
+            Ok(outcome)
+        }
+        Wait(Some(pid)) => {
+            let pid = process_id(pid, env_vars)?;
+            match env_vars.remove_job(pid) {
+                Some(job) => job.wait(),
+                None => Err(Error::Why("No such background job")),
+            }
+        }
+        Kill(signal, pid) => {
+            let signal = match signal {
+                Some(signal) => match signal.strip_prefix('-') {
+                    Some(signal) => signal_number(signal)?,
+                    None => return Err(Error::Why("Malformed signal")),
+                },
+                None => libc::SIGTERM,
+            };
+            let pid = process_id(pid, env_vars)?;
+            // SAFETY: kill() has no memory safety requirements
+            if unsafe { libc::kill(pid as libc::pid_t, signal) } != 0 {
+                return Err(std::io::Error::last_os_error().into());
+            }
+            Ok(Outcome::default())
+        }
+        Sequence(first, second) => {
+            let outcome = run_list_member(first, std_in, env_vars)?;
+            Ok(outcome.followed_by(run_list_member(second, std_in, env_vars)?))
+        }
+        AndList(first, second) => {
+            let outcome = run_list_member(first, std_in, env_vars)?;
+            if outcome.e_code == Some(0) {
+                Ok(outcome.followed_by(run_list_member(second, std_in, env_vars)?))
+            } else {
+                Ok(outcome)
+            }
         }
+        OrList(first, second) => {
+            let outcome = run_list_member(first, std_in, env_vars)?;
+            if outcome.e_code == Some(0) {
+                Ok(outcome)
+            } else {
+                Ok(outcome.followed_by(run_list_member(second, std_in, env_vars)?))
+            }
+        }
+        Default => Err(Error::Why("Uninitialized CommandAction")),
     }
 }
 
+// The here-documents in `cmd_action` (in the order that they appear) as
+// the delimiter (with quotes removed) and the body to be filled in.
+fn collect_here_docs<'a>(
+    cmd_action: &'a mut CommandAction,
+    here_docs: &mut Vec<(String, &'a mut String)>,
+) {
+    use CommandAction::*;
+    match cmd_action {
+        RunProgram(_, _, _, Some(Input::HereDoc(delimiter, body)), _, _)
+        | CreateFile(_, _, delimiter, body) => {
+            here_docs.push((remove_quotes(delimiter), body));
+        }
+        Shell(_, shell_here_docs) => {
+            for (delimiter, body) in shell_here_docs.iter_mut() {
+                here_docs.push((delimiter.clone(), body));
+            }
+        }
+        Pipeline(stages) | Background(stages) => {
+            for stage in stages.iter_mut() {
+                collect_here_docs(stage, here_docs);
+            }
+        }
+        Sequence(first, second) | AndList(first, second) | OrList(first, second) => {
+            collect_here_docs(first, here_docs);
+            collect_here_docs(second, here_docs);
+        }
+        _ => (),
+    }
+}
+
+// The (unquoted) delimiters of the here-documents ("<<WORD" or "<<-WORD"
+// outside quotes) in a shell command line in the order that they occur.
+fn shell_here_doc_delimiters(cmd_line: &str) -> Vec<String> {
+    let mut delimiters = vec![];
+    let mut quote = None;
+    let mut chars = cmd_line.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (Some('"') | None, '\\') => {
+                chars.next();
+            }
+            (Some(_), _) => (),
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '<') if cmd_line[index..].starts_with("<<") => {
+                chars.next();
+                if chars.next_if(|(_, c)| *c == '<').is_some() {
+                    // a here-string
+                    continue;
+                }
+                chars.next_if(|(_, c)| *c == '-');
+                while chars.next_if(|(_, c)| *c == ' ' || *c == '\t').is_some() {}
+                let start = chars.peek().map_or(cmd_line.len(), |(index, _)| *index);
+                let word = cmd_line[start..]
+                    .split(|c: char| c.is_whitespace() || ";|&<>()".contains(c))
+                    .next()
+                    .unwrap_or_default();
+                if !word.is_empty() {
+                    delimiters.push(remove_quotes(word));
+                }
+            }
+            _ => (),
+        }
+    }
+    delimiters
+}
+
 #[derive(Debug)]
 pub struct Command {
     pub cmd_line_string: String,
     pub cmd_action: CommandAction,
+    pub std_in: Option<String>,
 }
 
 impl Command {
     pub fn new(cmd_line_string: &str) -> Result<Self, &'static str> {
         let mut cmd_action: CommandAction = Default::default();
-        if cmd_action.parse_text(cmd_line_string, "command").is_err() {
+        if cmd_action
+            .parse_text(cmd_line_string.trim_end(), "command")
+            .is_err()
+        {
             return Err("Command not parseable");
         };
         Ok(Self {
             cmd_line_string: cmd_line_string.to_string(),
             cmd_action,
+            std_in: None,
         })
     }
 
+    // A command line to be run by a real shell (with the script's variables)
+    pub fn new_shell(cmd_line_string: &str) -> Self {
+        let cmd_line = cmd_line_string.trim_end();
+        let here_docs = shell_here_doc_delimiters(cmd_line)
+            .into_iter()
+            .map(|delimiter| (delimiter, String::new()))
+            .collect();
+        Self {
+            cmd_line_string: cmd_line_string.to_string(),
+            cmd_action: CommandAction::Shell(cmd_line.to_string(), here_docs),
+            std_in: None,
+        }
+    }
+
+    pub fn here_docs_mut(&mut self) -> Vec<(String, &mut String)> {
+        let mut here_docs = vec![];
+        collect_here_docs(&mut self.cmd_action, &mut here_docs);
+        here_docs
+    }
+
     pub fn run(&self, env_vars: &mut EnvVars) -> Result<Outcome, Error> {
-        use CommandAction::*;
-        match &self.cmd_action {
-            SetEnvVar(var, value) => {
-                env_vars.set_var(var, value);
-                Ok(Outcome::default())
-            }
-            UnsetEnvVar(var) => {
-                env_vars.remove_var(var);
-                Ok(Outcome::default())
-            }
-            ChangeDir(dir) => {
-                env::set_current_dir(dir)?;
-                env_vars.set_var("PWD", &env::current_dir()?.to_string_lossy());
-                Ok(Outcome::default())
-            }
-            RunProgram(program_name, args, input_path, output_path, err_output_path) => {
-                let input_file = match input_path {
-                    Some(path) => std::process::Stdio::from(std::fs::File::open(path)?),
-                    None => std::process::Stdio::null(),
-                };
-                let output_file = match output_path {
-                    Some((path, overwrite)) => {
-                        if *overwrite {
-                            std::process::Stdio::from(std::fs::File::create(path)?)
-                        } else {
-                            let file = std::fs::OpenOptions::new()
-                                .append(true)
-                                .create(true)
-                                .open(path)?;
-                            std::process::Stdio::from(file)
-                        }
-                    }
-                    None => std::process::Stdio::piped(),
-                };
-                let err_output_file = match err_output_path {
-                    Some((path, overwrite)) => {
-                        if *overwrite {
-                            std::process::Stdio::from(std::fs::File::create(path)?)
-                        } else {
-                            let file = std::fs::OpenOptions::new()
-                                .append(true)
-                                .create(true)
-                                .open(path)?;
-                            std::process::Stdio::from(file)
-                        }
-                    }
-                    None => std::process::Stdio::piped(),
-                };
-                Ok(Outcome::from(
-                    std::process::Command::new(program_name)
-                        .args(args.iter())
-                        .stdin(input_file)
-                        .stdout(output_file)
-                        .stderr(err_output_file)
-                        .envs(&env_vars.0)
-                        .output()?,
-                ))
-            }
-            Default => Err(Error::Why("Uninitialized CommandAction")),
+        match &self.std_in {
+            Some(std_in) => {
+                let (reader, mut writer) = std::io::pipe()?;
+                let data = std_in.clone();
+                // the programs may not read all of the input so write errors are ignored
+                let in_writer = thread::spawn(move || writer.write_all(data.as_bytes()).ok());
+                let outcome = run_action(&self.cmd_action, Some(&reader), env_vars);
+                // closing the reader releases the writer if the input wasn't all read
+                drop(reader);
+                in_writer.join().ok();
+                outcome
+            }
+            None => run_action(&self.cmd_action, None, env_vars),
         }
     }
 }
 
 #[cfg(test)]
 mod command_tests {
-    use crate::command::{Command, Outcome};
-    use crate::command_action::CommandAction;
+    use crate::command::{Command, Outcome, shell_here_doc_delimiters};
+    use crate::command_action::{CommandAction, Input, Output};
     use crate::script::EnvVars;
 
     #[test]
@@ -127,14 +638,15 @@ mod command_tests {
         use CommandAction::*;
         let cmd = Command::new("whatever x y < bbb > aaa").unwrap();
         match &cmd.cmd_action {
-            RunProgram(program_name, args, input_path, output_path, err_output_path) => {
+            RunProgram(assignments, program_name, args, input_path, output, err_output) => {
+                assert!(assignments.is_empty());
                 assert_eq!(program_name, "whatever");
                 assert_eq!(*args, ["x", "y"]);
-                assert_eq!(*input_path, Some("bbb".to_string()));
-                assert_eq!(*output_path, Some(("aaa".to_string(), true)));
-                assert_eq!(*err_output_path, None);
+                assert_eq!(*input_path, Some(Input::File("bbb".to_string())));
+                assert_eq!(*output, Some(Output::File("aaa".to_string(), true)));
+                assert_eq!(*err_output, None);
             }
-            _ => assert!(false),
+            _ => panic!("expected RunProgram"),
         }
         let env_vars = &mut EnvVars::new();
         let result = cmd.run(env_vars).unwrap_err().to_string();
@@ -151,10 +663,333 @@ mod command_tests {
             result.unwrap(),
             Outcome {
                 e_code: Some(0),
+                signal: None,
                 std_out: "".to_string(),
                 std_err: "".to_string(),
             }
         );
         assert_eq!(env_vars.var("MYNAME").unwrap(), "Peter");
     }
+
+    #[test]
+    fn pipeline_test() {
+        let cmd = Command::new("echo hello world | tr a-z A-Z").unwrap();
+        let env_vars = &mut EnvVars::new();
+        assert_eq!(
+            cmd.run(env_vars).unwrap(),
+            Outcome {
+                e_code: Some(0),
+                signal: None,
+                std_out: "HELLO WORLD\n".to_string(),
+                std_err: "".to_string(),
+            }
+        );
+
+        let cmd = Command::new("ls no_such_file | cat | false").unwrap();
+        let outcome = cmd.run(env_vars).unwrap();
+        assert_eq!(outcome.e_code, Some(1));
+        assert_eq!(outcome.std_out, "");
+        assert!(outcome.std_err.contains("no_such_file"));
+    }
+
+    #[test]
+    fn list_test() {
+        let env_vars = &mut EnvVars::new();
+        let cmd = Command::new("echo one && false && echo two").unwrap();
+        assert_eq!(
+            cmd.run(env_vars).unwrap(),
+            Outcome {
+                e_code: Some(1),
+                signal: None,
+                std_out: "one\n".to_string(),
+                std_err: "".to_string(),
+            }
+        );
+
+        let cmd = Command::new("false || echo two; echo three").unwrap();
+        assert_eq!(
+            cmd.run(env_vars).unwrap(),
+            Outcome {
+                e_code: Some(0),
+                signal: None,
+                std_out: "two\nthree\n".to_string(),
+                std_err: "".to_string(),
+            }
+        );
+
+        let cmd = Command::new("LIST_TEST=yes || LIST_TEST=no; false").unwrap();
+        assert_eq!(cmd.run(env_vars).unwrap().e_code, Some(1));
+        assert_eq!(env_vars.var("LIST_TEST").unwrap(), "yes");
+
+        let cmd = Command::new("cd /nonexistent || echo fallback").unwrap();
+        let outcome = cmd.run(env_vars).unwrap();
+        assert_eq!(
+            (outcome.e_code, outcome.std_out.as_str()),
+            (Some(0), "fallback\n")
+        );
+        assert!(!outcome.std_err.is_empty());
+
+        let cmd = Command::new("no_such_prog || echo $?").unwrap();
+        let outcome = cmd.run(env_vars).unwrap();
+        assert_eq!(
+            (outcome.e_code, outcome.std_out.as_str()),
+            (Some(0), "127\n")
+        );
+        assert!(outcome.std_err.starts_with("no_such_prog: "));
+
+        let cmd = Command::new("popd && echo popped; wait 1").unwrap();
+        let outcome = cmd.run(env_vars).unwrap();
+        assert_eq!((outcome.e_code, outcome.std_out.as_str()), (Some(1), ""));
+        assert_eq!(env_vars.var("?").unwrap(), "1");
+
+        // a command on its own that can't be run is still an error
+        assert!(Command::new("no_such_prog").unwrap().run(env_vars).is_err());
+    }
+
+    #[test]
+    fn expansion_test() {
+        let env_vars = &mut EnvVars::new();
+        env_vars.set_var("GREETING", "hello");
+        let cmd = Command::new("echo $GREETING ${NOBODY:-world}").unwrap();
+        assert_eq!(cmd.run(env_vars).unwrap().std_out, "hello world\n");
+
+        let cmd = Command::new("FAREWELL=${GREETING}.bye").unwrap();
+        cmd.run(env_vars).unwrap();
+        assert_eq!(env_vars.var("FAREWELL").unwrap(), "hello.bye");
+
+        let cmd = Command::new("ID=$(echo $FAREWELL | tr . -)").unwrap();
+        cmd.run(env_vars).unwrap();
+        assert_eq!(env_vars.var("ID").unwrap(), "hello-bye");
+        let cmd = Command::new("echo id:$(echo $ID)").unwrap();
+        assert_eq!(cmd.run(env_vars).unwrap().std_out, "id:hello-bye\n");
+    }
+
+    #[test]
+    fn quoting_test() {
+        let env_vars = &mut EnvVars::new();
+        env_vars.set_var("WORDS", "two  words");
+        let cmd = Command::new(r#"printf [%s] 'a b' "$WORDS" $WORDS \$WORDS --x="y z""#).unwrap();
+        assert_eq!(
+            cmd.run(env_vars).unwrap().std_out,
+            "[a b][two  words][two][words][$WORDS][--x=y z]"
+        );
+
+        let cmd = Command::new(r#"MSG="it's ${WORDS}""#).unwrap();
+        cmd.run(env_vars).unwrap();
+        assert_eq!(env_vars.var("MSG").unwrap(), "it's two  words");
+    }
+
+    #[test]
+    fn here_doc_test() {
+        let env_vars = &mut EnvVars::new();
+        env_vars.set_var("NAME", "world");
+        let mut cmd = Command::new("cat <<EOF | tr a-z A-Z").unwrap();
+        let mut here_docs = cmd.here_docs_mut();
+        assert_eq!(here_docs.len(), 1);
+        assert_eq!(here_docs[0].0, "EOF");
+        here_docs[0].1.push_str("hello $NAME\n'\\$NAME'\n");
+        assert_eq!(cmd.run(env_vars).unwrap().std_out, "HELLO WORLD\n'$NAME'\n");
+
+        let mut cmd = Command::new("cat <<'EOF'").unwrap();
+        let mut here_docs = cmd.here_docs_mut();
+        assert_eq!(here_docs[0].0, "EOF");
+        here_docs[0].1.push_str("hello $NAME\n");
+        assert_eq!(cmd.run(env_vars).unwrap().std_out, "hello $NAME\n");
+
+        let cmd = Command::new(r#"cat <<< "hello $NAME""#).unwrap();
+        assert_eq!(cmd.run(env_vars).unwrap().std_out, "hello world\n");
+    }
+
+    #[test]
+    fn std_in_test() {
+        let env_vars = &mut EnvVars::new();
+        let mut cmd = Command::new("tr a-z A-Z | sort").unwrap();
+        cmd.std_in = Some("hello\nworld\nabc\n".to_string());
+        assert_eq!(cmd.run(env_vars).unwrap().std_out, "ABC\nHELLO\nWORLD\n");
+
+        let mut cmd = Command::new("cat; echo middle; cat").unwrap();
+        cmd.std_in = Some("first\nsecond\n".to_string());
+        assert_eq!(
+            cmd.run(env_vars).unwrap().std_out,
+            "first\nsecond\nmiddle\n"
+        );
+
+        let mut cmd = Command::new("cat < /dev/null").unwrap();
+        cmd.std_in = Some("ignored\n".to_string());
+        assert_eq!(cmd.run(env_vars).unwrap().std_out, "");
+    }
+
+    #[test]
+    fn merge_output_test() {
+        let env_vars = &mut EnvVars::new();
+        let script = "echo one; echo two >&2; echo three";
+        let cmd = Command::new(&format!("sh -c '{script}' 2>&1")).unwrap();
+        let outcome = cmd.run(env_vars).unwrap();
+        assert_eq!(outcome.std_out, "one\ntwo\nthree\n");
+        assert_eq!(outcome.std_err, "");
+
+        let cmd = Command::new(&format!("sh -c '{script}' 1>&2")).unwrap();
+        let outcome = cmd.run(env_vars).unwrap();
+        assert_eq!(outcome.std_out, "");
+        assert_eq!(outcome.std_err, "one\ntwo\nthree\n");
+
+        let cmd = Command::new(&format!("sh -c '{script}' 2>&1 | tr a-z A-Z")).unwrap();
+        assert_eq!(cmd.run(env_vars).unwrap().std_out, "ONE\nTWO\nTHREE\n");
+
+        let dir = tempdir::TempDir::new("merge_output_test").unwrap();
+        let path = dir.path().join("merged").to_string_lossy().to_string();
+        let cmd = Command::new(&format!("sh -c '{script}' > {path} 2>&1")).unwrap();
+        assert_eq!(cmd.run(env_vars).unwrap(), Outcome::default());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "one\ntwo\nthree\n");
+
+        let cmd = Command::new(&format!("sh -c '{script}' >&2 2> {path}")).unwrap();
+        let outcome = cmd.run(env_vars).unwrap();
+        assert_eq!(outcome.std_err, "one\nthree\n");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "two\n");
+    }
+
+    #[test]
+    fn program_env_test() {
+        let env_vars = &mut EnvVars::new();
+        env_vars.set_var("GREETING", "hello");
+        let cmd = Command::new("GREETING=bye NAME=${GREETING}x printenv GREETING NAME").unwrap();
+        assert_eq!(cmd.run(env_vars).unwrap().std_out, "bye\nbyex\n");
+        assert_eq!(env_vars.var("GREETING").unwrap(), "hello");
+        assert_eq!(env_vars.var("NAME"), None);
+
+        // arguments are expanded before the assignments are made
+        let cmd = Command::new("GREETING=bye echo $GREETING").unwrap();
+        assert_eq!(cmd.run(env_vars).unwrap().std_out, "hello\n");
+
+        let cmd = Command::new("A=1 B=$A").unwrap();
+        cmd.run(env_vars).unwrap();
+        assert_eq!(env_vars.var("B").unwrap(), "1");
+    }
+
+    #[test]
+    fn special_var_test() {
+        let env_vars = &mut EnvVars::new();
+        let cmd = Command::new("false; echo $?; echo $?").unwrap();
+        assert_eq!(cmd.run(env_vars).unwrap().std_out, "1\n0\n");
+        assert_eq!(env_vars.var("?").unwrap(), "0");
+
+        env_vars.set_special("TESTDIR", "/tests");
+        let cmd = Command::new("printenv TESTDIR || echo $TESTDIR/x").unwrap();
+        assert_eq!(cmd.run(env_vars).unwrap().std_out, "/tests/x\n");
+        for cmd_line in ["TESTDIR=x", "unset TESTDIR", "TESTDIR=x echo"] {
+            let cmd = Command::new(cmd_line).unwrap();
+            assert!(cmd.run(env_vars).is_err());
+        }
+        assert_eq!(env_vars.var("TESTDIR").unwrap(), "/tests");
+    }
+
+    #[test]
+    fn create_file_test() {
+        use std::os::unix::fs::PermissionsExt;
+        let env_vars = &mut EnvVars::new();
+        let dir = tempdir::TempDir::new("create_file_test").unwrap();
+        env_vars.set_var("DIR", &dir.path().to_string_lossy());
+        let mut cmd = Command::new("fixture $DIR/sub/script.sh 750 <<EOF").unwrap();
+        cmd.here_docs_mut()[0].1.push_str("#!/bin/sh\necho $0\n");
+        assert_eq!(cmd.run(env_vars).unwrap(), Outcome::default());
+        let path = dir.path().join("sub/script.sh");
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "#!/bin/sh\necho $0\n"
+        );
+        let metadata = std::fs::metadata(&path).unwrap();
+        assert_eq!(metadata.permissions().mode() & 0o777, 0o750);
+
+        let cmd = Command::new("fixture $DIR/bad 9x <<EOF").unwrap();
+        assert!(cmd.run(env_vars).is_err());
+        assert!(!dir.path().join("bad").exists());
+    }
+
+    #[test]
+    fn background_test() {
+        let env_vars = &mut EnvVars::new();
+        let cmd = Command::new("sh -c 'echo started; echo oops >&2; exit 3' &").unwrap();
+        assert_eq!(cmd.run(env_vars).unwrap(), Outcome::default());
+        let pid = env_vars.var("!").unwrap().clone();
+        let cmd = Command::new("wait $!").unwrap();
+        assert_eq!(
+            cmd.run(env_vars).unwrap(),
+            Outcome {
+                e_code: Some(3),
+                signal: None,
+                std_out: "started\n".to_string(),
+                std_err: "oops\n".to_string(),
+            }
+        );
+        assert!(
+            Command::new(&format!("wait {pid}"))
+                .unwrap()
+                .run(env_vars)
+                .is_err()
+        );
+
+        let cmd = Command::new("sleep 60 &").unwrap();
+        cmd.run(env_vars).unwrap();
+        let cmd = Command::new("kill -SIGTERM $!; wait").unwrap();
+        assert_eq!(cmd.run(env_vars).unwrap(), Outcome::default());
+        let cmd = Command::new("sleep 60 &").unwrap();
+        cmd.run(env_vars).unwrap();
+        let cmd = Command::new("kill -9 $!; wait $!").unwrap();
+        let outcome = cmd.run(env_vars).unwrap();
+        assert_eq!(
+            (outcome.e_code, outcome.signal),
+            (None, Some(libc::SIGKILL))
+        );
+        assert_eq!(env_vars.var("?").unwrap(), "137");
+        // left running to be killed when env_vars is dropped
+        let cmd = Command::new("sleep 60 | cat &").unwrap();
+        cmd.run(env_vars).unwrap();
+        assert!(
+            Command::new("kill -NOSUCH 1")
+                .unwrap()
+                .run(env_vars)
+                .is_err()
+        );
+        assert!(Command::new("kill 0").unwrap().run(env_vars).is_err());
+
+        let mut cmd = Command::new("cat <<EOF &").unwrap();
+        cmd.here_docs_mut()[0].1.push_str("in the background\n");
+        cmd.run(env_vars).unwrap();
+        let cmd = Command::new("wait $!").unwrap();
+        assert_eq!(cmd.run(env_vars).unwrap().std_out, "in the background\n");
+    }
+
+    #[test]
+    fn shell_test() {
+        let env_vars = &mut EnvVars::new();
+        env_vars.set_var("WORDS", "a b");
+        let cmd = Command::new_shell("for w in $WORDS; do echo \"<$w>\"; done 2>&1\n");
+        assert_eq!(cmd.run(env_vars).unwrap().std_out, "<a>\n<b>\n");
+
+        let mut cmd = Command::new_shell("f() { tr a-z A-Z; exit 4; }; f");
+        cmd.std_in = Some("hello\n".to_string());
+        assert_eq!(
+            cmd.run(env_vars).unwrap(),
+            Outcome {
+                e_code: Some(4),
+                signal: None,
+                std_out: "HELLO\n".to_string(),
+                std_err: "".to_string(),
+            }
+        );
+        assert_eq!(env_vars.var("?").unwrap(), "4");
+
+        let delimiters =
+            shell_here_doc_delimiters("tr a-z A-Z <<<'no'; cat <<-E1 && cat << \"E2\" # '<<E3'");
+        assert_eq!(delimiters, ["E1", "E2"]);
+
+        let mut cmd = Command::new_shell("cat <<'END' && echo '<<X' && cat << \"X\"\n");
+        for (delimiter, body) in cmd.here_docs_mut() {
+            body.push_str(&format!("> $HOME {delimiter}\n"));
+        }
+        assert_eq!(
+            cmd.run(env_vars).unwrap().std_out,
+            "> $HOME END\n<<X\n> $HOME X\n"
+        );
+    }
 }
@@ -3,22 +3,58 @@
 // Copyright 2026 (c) Peter Williams <pwil3058@bigpond.net.au> <pwil3058@gmail.com>
 //
 #[allow(dead_code)]
-#[derive(Debug, Default, PartialEq, Eq)]
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
 pub enum CommandAction {
     SetEnvVar(String, String),
     UnsetEnvVar(String),
-    ChangeDir(String),
+    // No directory means $HOME and "-" means $OLDPWD
+    ChangeDir(Option<String>),
+    PushDir(String),
+    PopDir,
+    // The path, mode, (unexpanded) here-document delimiter and contents of a file to be created
+    CreateFile(String, Option<String>, String, String),
+    // The environment assignments for this program only, name, arguments and redirections
     RunProgram(
+        Vec<(String, String)>,
         String,
         Vec<String>,
-        Option<String>,
-        Option<(String, bool)>,
-        Option<(String, bool)>,
+        Option<Input>,
+        Option<Output>,
+        Option<Output>,
     ),
+    Pipeline(Vec<CommandAction>),
+    // A command line to be run by "sh -c" and the (unquoted) delimiters and
+    // bodies of its here-documents
+    Shell(String, Vec<(String, String)>),
+    // The stages of a pipeline to be run in the background
+    Background(Vec<CommandAction>),
+    // The (unexpanded) process id of the job to wait for (or all jobs)
+    Wait(Option<String>),
+    // The (unexpanded) signal and process id
+    Kill(Option<String>, String),
+    Sequence(Box<CommandAction>, Box<CommandAction>),
+    AndList(Box<CommandAction>, Box<CommandAction>),
+    OrList(Box<CommandAction>, Box<CommandAction>),
     #[default]
     Default,
 }
 
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Input {
+    File(String),
+    // The (unexpanded) delimiter and the body of a here-document
+    HereDoc(String, String),
+    HereString(String),
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Output {
+    // The path and whether it should be overwritten (rather than appended to)
+    File(String, bool),
+    StdOut,
+    StdErr,
+}
+
 #[cfg(test)]
 pub mod parser_tests;
 
@@ -29,11 +65,13 @@ impl lalr1::ReportError<AATerminal> for CommandAction {}
 pub enum AttributeData {
     Token(lexan::Token<AATerminal>),
     Error(lalr1::Error<AATerminal>),
-    String(String),
-    Id(String),
+    Word(String),
     Args(Vec<String>),
-    Input(Option<String>),
-    Output(Option<(String, bool)>),
+    Assignments(Vec<(String, String)>),
+    Input(Option<Input>),
+    Output(Option<Output>),
+    Action(CommandAction),
+    Stages(Vec<CommandAction>),
     #[default]
     Default,
 }
@@ -53,30 +91,68 @@ impl AttributeData {
         }
     }
 
-    fn input(&self) -> Option<String> {
+    fn assignments(&self) -> Vec<(String, String)> {
+        match self {
+            Self::Assignments(assignments) => assignments.clone(),
+            _ => panic!("Invalid AttributeData variant"),
+        }
+    }
+
+    fn assignments_mut(&mut self) -> &mut Vec<(String, String)> {
+        match self {
+            Self::Assignments(assignments) => assignments,
+            _ => panic!("Invalid AttributeData variant"),
+        }
+    }
+
+    fn input(&self) -> Option<Input> {
         match self {
             Self::Input(input) => input.clone(),
             _ => panic!("Invalid AttributeData variant"),
         }
     }
 
-    fn output(&self) -> Option<(String, bool)> {
+    fn output(&self) -> Option<Output> {
         match self {
             Self::Output(output) => output.clone(),
             _ => panic!("Invalid AttributeData variant"),
         }
     }
 
-    fn string(&self) -> String {
+    fn action(&self) -> CommandAction {
+        match self {
+            Self::Action(action) => action.clone(),
+            _ => panic!("Invalid AttributeData variant"),
+        }
+    }
+
+    fn stages(&self) -> Vec<CommandAction> {
+        match self {
+            Self::Stages(stages) => stages.clone(),
+            _ => panic!("Invalid AttributeData variant"),
+        }
+    }
+
+    fn stages_mut(&mut self) -> &mut Vec<CommandAction> {
+        match self {
+            Self::Stages(stages) => stages,
+            _ => panic!("Invalid AttributeData variant"),
+        }
+    }
+
+    fn word(&self) -> String {
         match self {
-            Self::String(string) => string.clone(),
+            Self::Word(word) => word.clone(),
             _ => panic!("Invalid AttributeData variant"),
         }
     }
 
-    fn id(&self) -> String {
+    fn assignment(&self) -> (String, String) {
         match self {
-            Self::Id(string) => string.clone(),
+            Self::Word(word) => match word.split_once('=') {
+                Some((name, value)) => (name.to_string(), value.to_string()),
+                None => panic!("Invalid assignment"),
+            },
             _ => panic!("Invalid AttributeData variant"),
         }
     }
@@ -85,8 +161,14 @@ impl AttributeData {
 impl From<lexan::Token<AATerminal>> for AttributeData {
     fn from(input: lexan::Token<AATerminal>) -> Self {
         match input.tag() {
-            AATerminal::String => AttributeData::String(input.lexeme().to_string()),
-            AATerminal::Id => AttributeData::Id(input.lexeme().to_string()),
+            // built-in names are ordinary words when they are arguments
+            AATerminal::Assignment
+            | AATerminal::Word
+            | AATerminal::PushDir
+            | AATerminal::PopDir
+            | AATerminal::Fixture
+            | AATerminal::Wait
+            | AATerminal::Kill => AttributeData::Word(input.lexeme().to_string()),
             _ => AttributeData::Token(input.clone()),
         }
     }
@@ -117,34 +199,62 @@ macro_rules! ordered_set {
 #[derive(Debug, Clone, Copy, PartialOrd, Ord, PartialEq, Eq)]
 pub enum AATerminal {
     AAEnd,
+    And,
     Append,
-    Assign,
+    Assignment,
+    Background,
     ChangeDir,
     EAppend,
     EOverWrite,
     Eol,
-    Id,
+    ErrToOut,
+    Fixture,
+    HereDoc,
+    HereString,
     InputFile,
+    Kill,
+    Or,
+    OutToErr,
+    OutToErr1,
     OverWrite,
-    String,
+    Pipe,
+    PopDir,
+    PushDir,
+    Semicolon,
     Unset,
+    Wait,
+    Word,
 }
 
 impl std::fmt::Display for AATerminal {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
             AATerminal::AAEnd => write!(f, r###"AAEnd"###),
+            AATerminal::And => write!(f, r###""&&""###),
             AATerminal::Append => write!(f, r###"">>""###),
-            AATerminal::Assign => write!(f, r###""=""###),
+            AATerminal::Assignment => write!(f, r###"Assignment"###),
+            AATerminal::Background => write!(f, r###""&""###),
             AATerminal::ChangeDir => write!(f, r###""cd""###),
             AATerminal::EAppend => write!(f, r###""2>>""###),
             AATerminal::EOverWrite => write!(f, r###""2>""###),
             AATerminal::Eol => write!(f, r###"Eol"###),
-            AATerminal::Id => write!(f, r###"Id"###),
+            AATerminal::ErrToOut => write!(f, r###""2>&1""###),
+            AATerminal::Fixture => write!(f, r###""fixture""###),
+            AATerminal::HereDoc => write!(f, r###""<<""###),
+            AATerminal::HereString => write!(f, r###""<<<""###),
             AATerminal::InputFile => write!(f, r###""<""###),
+            AATerminal::Kill => write!(f, r###""kill""###),
+            AATerminal::Or => write!(f, r###""||""###),
+            AATerminal::OutToErr => write!(f, r###"">&2""###),
+            AATerminal::OutToErr1 => write!(f, r###""1>&2""###),
             AATerminal::OverWrite => write!(f, r###"">""###),
-            AATerminal::String => write!(f, r###"String"###),
+            AATerminal::Pipe => write!(f, r###""|""###),
+            AATerminal::PopDir => write!(f, r###""popd""###),
+            AATerminal::PushDir => write!(f, r###""pushd""###),
+            AATerminal::Semicolon => write!(f, r###"";""###),
             AATerminal::Unset => write!(f, r###""unset""###),
+            AATerminal::Wait => write!(f, r###""wait""###),
+            AATerminal::Word => write!(f, r###"Word"###),
         }
     }
 }
@@ -154,18 +264,32 @@ lazy_static::lazy_static! {
         use AATerminal::*;
         lexan::LexicalAnalyzer::new(
             &[
+                (Background, r###"&"###),
+                (And, r###"&&"###),
+                (OutToErr1, r###"1>&2"###),
                 (EOverWrite, r###"2>"###),
+                (ErrToOut, r###"2>&1"###),
                 (EAppend, r###"2>>"###),
+                (Semicolon, r###";"###),
                 (InputFile, r###"<"###),
-                (Assign, r###"="###),
+                (HereDoc, r###"<<"###),
+                (HereString, r###"<<<"###),
                 (OverWrite, r###">"###),
+                (OutToErr, r###">&2"###),
                 (Append, r###">>"###),
                 (ChangeDir, r###"cd"###),
+                (Fixture, r###"fixture"###),
+                (Kill, r###"kill"###),
+                (PopDir, r###"popd"###),
+                (PushDir, r###"pushd"###),
                 (Unset, r###"unset"###),
+                (Wait, r###"wait"###),
+                (Pipe, r###"|"###),
+                (Or, r###"||"###),
             ],
             &[
-                (String, r###"("(\\"|[^"\r\n\v\f])+")"###),
-                (Id, r###"([\w\d._\-/:]+)"###),
+                (Word, r###"([A-Za-z_][A-Za-z0-9_]*((\\.|'[^']*'|"(\\.|[^"\\])*"|\$\{[^}\n]*\}|\$\(([^()'"\n]|'[^'\n]*'|"(\\.|[^"\\\n])*"|\([^()\n]*\))*\)|[^\s"'\\|&;<>()A-Za-z0-9_=])(\\.|'[^']*'|"(\\.|[^"\\])*"|\$\{[^}\n]*\}|\$\(([^()'"\n]|'[^'\n]*'|"(\\.|[^"\\\n])*"|\([^()\n]*\))*\)|[^\s"'\\|&;<>()])*)?|(\\.|'[^']*'|"(\\.|[^"\\])*"|\$\{[^}\n]*\}|\$\(([^()'"\n]|'[^'\n]*'|"(\\.|[^"\\\n])*"|\([^()\n]*\))*\)|[^\s"'\\|&;<>()A-Za-z_])(\\.|'[^']*'|"(\\.|[^"\\])*"|\$\{[^}\n]*\}|\$\(([^()'"\n]|'[^'\n]*'|"(\\.|[^"\\\n])*"|\([^()\n]*\))*\)|[^\s"'\\|&;<>()])*)"###),
+                (Assignment, r###"([A-Za-z_][A-Za-z0-9_]*=(\\.|'[^']*'|"(\\.|[^"\\])*"|\$\{[^}\n]*\}|\$\(([^()'"\n]|'[^'\n]*'|"(\\.|[^"\\\n])*"|\([^()\n]*\))*\)|[^\s"'\\|&;<>()])*)"###),
                 (Eol, r###"(\n)"###),
             ],
             &[
@@ -179,22 +303,34 @@ lazy_static::lazy_static! {
 #[derive(Debug, Clone, Copy, PartialOrd, Ord, PartialEq, Eq)]
 pub enum AANonTerminal {
     AAStart,
+    AndOr,
     Args,
+    Assignments,
     CommandAction,
     ErrOutput,
     Input,
+    List,
     Output,
+    Pipeline,
+    Program,
+    Simple,
 }
 
 impl std::fmt::Display for AANonTerminal {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
             AANonTerminal::AAStart => write!(f, r"AAStart"),
+            AANonTerminal::AndOr => write!(f, r"AndOr"),
             AANonTerminal::Args => write!(f, r"Args"),
+            AANonTerminal::Assignments => write!(f, r"Assignments"),
             AANonTerminal::CommandAction => write!(f, r"CommandAction"),
             AANonTerminal::ErrOutput => write!(f, r"ErrOutput"),
             AANonTerminal::Input => write!(f, r"Input"),
+            AANonTerminal::List => write!(f, r"List"),
             AANonTerminal::Output => write!(f, r"Output"),
+            AANonTerminal::Pipeline => write!(f, r"Pipeline"),
+            AANonTerminal::Program => write!(f, r"Program"),
+            AANonTerminal::Simple => write!(f, r"Simple"),
         }
     }
 }
@@ -211,38 +347,159 @@ impl lalr1::Parser<AATerminal, AANonTerminal, AttributeData> for CommandAction {
     fn look_ahead_set(state: u32) -> OrderedSet<AATerminal> {
         use AATerminal::*;
         match state {
-            0 => ordered_set![ChangeDir, Unset, Id],
+            0 => ordered_set![
+                ChangeDir, Fixture, Kill, PopDir, PushDir, Unset, Wait, Assignment, Word
+            ],
             1 => ordered_set![AAEnd],
-            2 => ordered_set![
-                Append, Assign, EAppend, EOverWrite, InputFile, OverWrite, Id, String, AAEnd
+            2 => ordered_set![Semicolon, AAEnd],
+            3 => ordered_set![And, Background, Or, Pipe, Semicolon, AAEnd],
+            4 => ordered_set![And, Or, Semicolon, AAEnd],
+            5 => ordered_set![And, Or, Semicolon, AAEnd],
+            6 => ordered_set![And, Or, Semicolon, Assignment, Word, AAEnd],
+            7 => ordered_set![Word],
+            8 => ordered_set![And, Or, Semicolon, Word, AAEnd],
+            9 => ordered_set![Word],
+            10 => ordered_set![And, Or, Semicolon, AAEnd],
+            11 => ordered_set![And, Or, Semicolon, Word, AAEnd],
+            12 => ordered_set![Word],
+            13 => ordered_set![Word],
+            14 => ordered_set![And, Background, Or, Pipe, Semicolon, AAEnd],
+            15 => ordered_set![
+                And, Append, Background, EAppend, EOverWrite, ErrToOut, Fixture, HereDoc,
+                HereString, InputFile, Kill, Or, OutToErr, OutToErr1, OverWrite, Pipe, PopDir,
+                PushDir, Semicolon, Wait, Assignment, Word, AAEnd
             ],
-            3 => ordered_set![Id],
-            4 => ordered_set![Id],
-            5 => ordered_set![Id, String],
-            6 => ordered_set![Append, EAppend, EOverWrite, InputFile, OverWrite, Id, String, AAEnd],
-            7 => ordered_set![AAEnd],
-            8 => ordered_set![AAEnd],
-            9 => ordered_set![AAEnd],
-            10 => ordered_set![AAEnd],
-            11 => ordered_set![Append, EAppend, EOverWrite, OverWrite, AAEnd],
-            12 => {
-                ordered_set![Append, EAppend, EOverWrite, InputFile, OverWrite, Id, String, AAEnd]
-            }
-            13 => {
-                ordered_set![Append, EAppend, EOverWrite, InputFile, OverWrite, Id, String, AAEnd]
-            }
-            14 => ordered_set![Id],
-            15 => ordered_set![EAppend, EOverWrite, AAEnd],
-            16 => ordered_set![Id],
-            17 => ordered_set![Id],
-            18 => ordered_set![Append, EAppend, EOverWrite, OverWrite, AAEnd],
-            19 => ordered_set![AAEnd],
-            20 => ordered_set![Id],
-            21 => ordered_set![Id],
-            22 => ordered_set![EAppend, EOverWrite, AAEnd],
-            23 => ordered_set![EAppend, EOverWrite, AAEnd],
-            24 => ordered_set![AAEnd],
-            25 => ordered_set![AAEnd],
+            16 => ordered_set![And, Or, Semicolon, Assignment, Word, AAEnd],
+            17 => ordered_set![
+                ChangeDir, Fixture, Kill, PopDir, PushDir, Unset, Wait, Assignment, Word, AAEnd
+            ],
+            18 => ordered_set![AAEnd],
+            19 => ordered_set![Assignment, Word],
+            20 => ordered_set![
+                ChangeDir, Fixture, Kill, PopDir, PushDir, Unset, Wait, Assignment, Word
+            ],
+            21 => ordered_set![
+                ChangeDir, Fixture, Kill, PopDir, PushDir, Unset, Wait, Assignment, Word
+            ],
+            22 => ordered_set![
+                And, Append, Background, EAppend, EOverWrite, ErrToOut, Fixture, HereDoc,
+                HereString, InputFile, Kill, Or, OutToErr, OutToErr1, OverWrite, Pipe, PopDir,
+                PushDir, Semicolon, Wait, Assignment, Word, AAEnd
+            ],
+            23 => ordered_set![And, Or, Semicolon, Assignment, Word, AAEnd],
+            24 => ordered_set![And, Or, Semicolon, AAEnd],
+            25 => ordered_set![And, Or, Semicolon, AAEnd],
+            26 => ordered_set![And, Or, Semicolon, AAEnd],
+            27 => ordered_set![And, Or, Semicolon, AAEnd],
+            28 => ordered_set![And, Or, Semicolon, Word, AAEnd],
+            29 => ordered_set![HereDoc, Word],
+            30 => ordered_set![
+                And, Append, Background, EAppend, EOverWrite, ErrToOut, Fixture, HereDoc,
+                HereString, InputFile, Kill, Or, OutToErr, OutToErr1, OverWrite, Pipe, PopDir,
+                PushDir, Semicolon, Wait, Assignment, Word, AAEnd
+            ],
+            31 => ordered_set![And, Or, Semicolon, AAEnd],
+            32 => ordered_set![And, Or, Pipe, Semicolon, AAEnd],
+            33 => ordered_set![And, Background, Or, Pipe, Semicolon, AAEnd],
+            34 => ordered_set![Assignment, Word],
+            35 => ordered_set![And, Or, Semicolon, AAEnd],
+            36 => ordered_set![And, Or, Semicolon, AAEnd],
+            37 => ordered_set![
+                And, Append, Background, EAppend, EOverWrite, ErrToOut, Fixture, HereDoc,
+                HereString, InputFile, Kill, Or, OutToErr, OutToErr1, OverWrite, Pipe, PopDir,
+                PushDir, Semicolon, Wait, Assignment, Word, AAEnd
+            ],
+            38 => ordered_set![And, Or, Semicolon, AAEnd],
+            39 => ordered_set![Word],
+            40 => ordered_set![HereDoc],
+            41 => ordered_set![
+                And, Append, Background, EAppend, EOverWrite, ErrToOut, Or, OutToErr, OutToErr1,
+                OverWrite, Pipe, Semicolon, AAEnd
+            ],
+            42 => ordered_set![
+                And, Append, Background, EAppend, EOverWrite, ErrToOut, Fixture, HereDoc,
+                HereString, InputFile, Kill, Or, OutToErr, OutToErr1, OverWrite, Pipe, PopDir,
+                PushDir, Semicolon, Wait, Assignment, Word, AAEnd
+            ],
+            43 => ordered_set![
+                And, Append, Background, EAppend, EOverWrite, ErrToOut, Fixture, HereDoc,
+                HereString, InputFile, Kill, Or, OutToErr, OutToErr1, OverWrite, Pipe, PopDir,
+                PushDir, Semicolon, Wait, Assignment, Word, AAEnd
+            ],
+            44 => ordered_set![
+                And, Append, Background, EAppend, EOverWrite, ErrToOut, Fixture, HereDoc,
+                HereString, InputFile, Kill, Or, OutToErr, OutToErr1, OverWrite, Pipe, PopDir,
+                PushDir, Semicolon, Wait, Assignment, Word, AAEnd
+            ],
+            45 => ordered_set![
+                And, Append, Background, EAppend, EOverWrite, ErrToOut, Fixture, HereDoc,
+                HereString, InputFile, Kill, Or, OutToErr, OutToErr1, OverWrite, Pipe, PopDir,
+                PushDir, Semicolon, Wait, Assignment, Word, AAEnd
+            ],
+            46 => ordered_set![
+                And, Append, Background, EAppend, EOverWrite, ErrToOut, Fixture, HereDoc,
+                HereString, InputFile, Kill, Or, OutToErr, OutToErr1, OverWrite, Pipe, PopDir,
+                PushDir, Semicolon, Wait, Assignment, Word, AAEnd
+            ],
+            47 => ordered_set![
+                And, Append, Background, EAppend, EOverWrite, ErrToOut, Fixture, HereDoc,
+                HereString, InputFile, Kill, Or, OutToErr, OutToErr1, OverWrite, Pipe, PopDir,
+                PushDir, Semicolon, Wait, Assignment, Word, AAEnd
+            ],
+            48 => ordered_set![
+                And, Append, Background, EAppend, EOverWrite, ErrToOut, Fixture, HereDoc,
+                HereString, InputFile, Kill, Or, OutToErr, OutToErr1, OverWrite, Pipe, PopDir,
+                PushDir, Semicolon, Wait, Assignment, Word, AAEnd
+            ],
+            49 => ordered_set![Word],
+            50 => ordered_set![Word],
+            51 => ordered_set![Word],
+            52 => ordered_set![
+                And, Append, Background, EAppend, EOverWrite, ErrToOut, Or, OutToErr, OutToErr1,
+                OverWrite, Pipe, Semicolon, AAEnd
+            ],
+            53 => ordered_set![And, Or, Semicolon, AAEnd],
+            54 => ordered_set![Word],
+            55 => ordered_set![
+                And, Background, EAppend, EOverWrite, ErrToOut, Or, Pipe, Semicolon, AAEnd
+            ],
+            56 => ordered_set![Word],
+            57 => ordered_set![Word],
+            58 => ordered_set![
+                And, Background, EAppend, EOverWrite, ErrToOut, Or, Pipe, Semicolon, AAEnd
+            ],
+            59 => ordered_set![
+                And, Background, EAppend, EOverWrite, ErrToOut, Or, Pipe, Semicolon, AAEnd
+            ],
+            60 => ordered_set![
+                And, Append, Background, EAppend, EOverWrite, ErrToOut, Or, OutToErr, OutToErr1,
+                OverWrite, Pipe, Semicolon, AAEnd
+            ],
+            61 => ordered_set![
+                And, Append, Background, EAppend, EOverWrite, ErrToOut, Or, OutToErr, OutToErr1,
+                OverWrite, Pipe, Semicolon, AAEnd
+            ],
+            62 => ordered_set![
+                And, Append, Background, EAppend, EOverWrite, ErrToOut, Or, OutToErr, OutToErr1,
+                OverWrite, Pipe, Semicolon, AAEnd
+            ],
+            63 => ordered_set![
+                And, Background, EAppend, EOverWrite, ErrToOut, Or, Pipe, Semicolon, AAEnd
+            ],
+            64 => ordered_set![And, Or, Semicolon, AAEnd],
+            65 => ordered_set![And, Background, Or, Pipe, Semicolon, AAEnd],
+            66 => ordered_set![Word],
+            67 => ordered_set![Word],
+            68 => ordered_set![And, Background, Or, Pipe, Semicolon, AAEnd],
+            69 => ordered_set![
+                And, Background, EAppend, EOverWrite, ErrToOut, Or, Pipe, Semicolon, AAEnd
+            ],
+            70 => ordered_set![
+                And, Background, EAppend, EOverWrite, ErrToOut, Or, Pipe, Semicolon, AAEnd
+            ],
+            71 => ordered_set![And, Background, Or, Pipe, Semicolon, AAEnd],
+            72 => ordered_set![And, Background, Or, Pipe, Semicolon, AAEnd],
+            73 => ordered_set![And, Background, Or, Pipe, Semicolon, AAEnd],
             _ => panic!("illegal state: {state}"),
         }
     }
@@ -253,9 +510,15 @@ impl lalr1::Parser<AATerminal, AANonTerminal, AttributeData> for CommandAction {
         let aa_tag = *aa_token.tag();
         match aa_state {
             0 => match aa_tag {
-                ChangeDir => Action::Shift(4),
-                Unset => Action::Shift(3),
-                Id => Action::Shift(2),
+                ChangeDir => Action::Shift(8),
+                Fixture => Action::Shift(13),
+                Kill => Action::Shift(12),
+                PopDir => Action::Shift(10),
+                PushDir => Action::Shift(9),
+                Unset => Action::Shift(7),
+                Wait => Action::Shift(11),
+                Assignment => Action::Shift(16),
+                Word => Action::Shift(15),
                 _ => Action::SyntaxError,
             },
             1 => match aa_tag {
@@ -264,130 +527,466 @@ impl lalr1::Parser<AATerminal, AANonTerminal, AttributeData> for CommandAction {
                 _ => Action::SyntaxError,
             },
             2 => match aa_tag {
-                Assign => Action::Shift(5),
-                // Args: <empty> #(NonAssoc, 0)
-                Append | EAppend | EOverWrite | InputFile | OverWrite | Id | String | AAEnd => {
-                    Action::Reduce(6)
-                }
+                Semicolon => Action::Shift(17),
+                // CommandAction: List #(NonAssoc, 0)
+                AAEnd => Action::Reduce(1),
                 _ => Action::SyntaxError,
             },
             3 => match aa_tag {
-                Id => Action::Shift(7),
+                Background => Action::Shift(18),
+                Pipe => Action::Shift(19),
+                // Simple: Pipeline #(NonAssoc, 0)
+                And | Or | Semicolon | AAEnd => Action::Reduce(21),
                 _ => Action::SyntaxError,
             },
             4 => match aa_tag {
-                Id => Action::Shift(8),
+                And => Action::Shift(20),
+                Or => Action::Shift(21),
+                // List: AndOr #(NonAssoc, 0)
+                Semicolon | AAEnd => Action::Reduce(4),
                 _ => Action::SyntaxError,
             },
             5 => match aa_tag {
-                Id => Action::Shift(9),
-                String => Action::Shift(10),
+                // AndOr: Simple #(NonAssoc, 0)
+                And | Or | Semicolon | AAEnd => Action::Reduce(6),
                 _ => Action::SyntaxError,
             },
             6 => match aa_tag {
-                InputFile => Action::Shift(14),
-                Id => Action::Shift(12),
-                String => Action::Shift(13),
-                // Input: <empty> #(NonAssoc, 0)
-                Append | EAppend | EOverWrite | OverWrite | AAEnd => Action::Reduce(9),
+                Assignment => Action::Shift(23),
+                Word => Action::Shift(22),
+                // Simple: Assignments #(NonAssoc, 0)
+                And | Or | Semicolon | AAEnd => Action::Reduce(9),
                 _ => Action::SyntaxError,
             },
             7 => match aa_tag {
-                // CommandAction: "unset" Id #(NonAssoc, 0)
-                AAEnd => Action::Reduce(3),
+                Word => Action::Shift(24),
                 _ => Action::SyntaxError,
             },
             8 => match aa_tag {
-                // CommandAction: "cd" Id #(NonAssoc, 0)
-                AAEnd => Action::Reduce(4),
+                Word => Action::Shift(25),
+                // Simple: "cd" #(NonAssoc, 0)
+                And | Or | Semicolon | AAEnd => Action::Reduce(11),
                 _ => Action::SyntaxError,
             },
             9 => match aa_tag {
-                // CommandAction: Id "=" Id #(NonAssoc, 0)
-                AAEnd => Action::Reduce(1),
+                Word => Action::Shift(26),
                 _ => Action::SyntaxError,
             },
             10 => match aa_tag {
-                // CommandAction: Id "=" String #(NonAssoc, 0)
-                AAEnd => Action::Reduce(2),
+                // Simple: "popd" #(NonAssoc, 0)
+                And | Or | Semicolon | AAEnd => Action::Reduce(14),
                 _ => Action::SyntaxError,
             },
             11 => match aa_tag {
-                Append => Action::Shift(17),
-                OverWrite => Action::Shift(16),
-                // Output: <empty> #(NonAssoc, 0)
-                EAppend | EOverWrite | AAEnd => Action::Reduce(11),
+                Word => Action::Shift(27),
+                // Simple: "wait" #(NonAssoc, 0)
+                And | Or | Semicolon | AAEnd => Action::Reduce(15),
                 _ => Action::SyntaxError,
             },
             12 => match aa_tag {
-                // Args: Args Id #(NonAssoc, 0)
-                Append | EAppend | EOverWrite | InputFile | OverWrite | Id | String | AAEnd => {
-                    Action::Reduce(7)
-                }
+                Word => Action::Shift(28),
                 _ => Action::SyntaxError,
             },
             13 => match aa_tag {
-                // Args: Args String #(NonAssoc, 0)
-                Append | EAppend | EOverWrite | InputFile | OverWrite | Id | String | AAEnd => {
-                    Action::Reduce(8)
-                }
+                Word => Action::Shift(29),
                 _ => Action::SyntaxError,
             },
             14 => match aa_tag {
-                Id => Action::Shift(18),
+                // Pipeline: Program #(NonAssoc, 0)
+                And | Background | Or | Pipe | Semicolon | AAEnd => Action::Reduce(22),
                 _ => Action::SyntaxError,
             },
             15 => match aa_tag {
-                EAppend => Action::Shift(21),
-                EOverWrite => Action::Shift(20),
-                // ErrOutput: <empty> #(NonAssoc, 0)
-                AAEnd => Action::Reduce(14),
+                // Args: <empty> #(NonAssoc, 0)
+                And | Append | Background | EAppend | EOverWrite | ErrToOut | Fixture | HereDoc
+                | HereString | InputFile | Kill | Or | OutToErr | OutToErr1 | OverWrite | Pipe
+                | PopDir | PushDir | Semicolon | Wait | Assignment | Word | AAEnd => {
+                    Action::Reduce(28)
+                }
                 _ => Action::SyntaxError,
             },
             16 => match aa_tag {
-                Id => Action::Shift(22),
+                // Assignments: Assignment #(NonAssoc, 0)
+                And | Or | Semicolon | Assignment | Word | AAEnd => Action::Reduce(26),
                 _ => Action::SyntaxError,
             },
             17 => match aa_tag {
-                Id => Action::Shift(23),
+                ChangeDir => Action::Shift(8),
+                Fixture => Action::Shift(13),
+                Kill => Action::Shift(12),
+                PopDir => Action::Shift(10),
+                PushDir => Action::Shift(9),
+                Unset => Action::Shift(7),
+                Wait => Action::Shift(11),
+                Assignment => Action::Shift(16),
+                Word => Action::Shift(15),
+                // CommandAction: List ";" #(NonAssoc, 0)
+                AAEnd => Action::Reduce(2),
                 _ => Action::SyntaxError,
             },
             18 => match aa_tag {
-                // Input: "<" Id #(NonAssoc, 0)
-                Append | EAppend | EOverWrite | OverWrite | AAEnd => Action::Reduce(10),
+                // CommandAction: Pipeline "&" #(NonAssoc, 0)
+                AAEnd => Action::Reduce(3),
                 _ => Action::SyntaxError,
             },
             19 => match aa_tag {
-                // CommandAction: Id Args Input Output ErrOutput #(NonAssoc, 0)
-                AAEnd => Action::Reduce(5),
+                Assignment => Action::Shift(16),
+                Word => Action::Shift(15),
                 _ => Action::SyntaxError,
             },
             20 => match aa_tag {
-                Id => Action::Shift(24),
+                ChangeDir => Action::Shift(8),
+                Fixture => Action::Shift(13),
+                Kill => Action::Shift(12),
+                PopDir => Action::Shift(10),
+                PushDir => Action::Shift(9),
+                Unset => Action::Shift(7),
+                Wait => Action::Shift(11),
+                Assignment => Action::Shift(16),
+                Word => Action::Shift(15),
                 _ => Action::SyntaxError,
             },
             21 => match aa_tag {
-                Id => Action::Shift(25),
+                ChangeDir => Action::Shift(8),
+                Fixture => Action::Shift(13),
+                Kill => Action::Shift(12),
+                PopDir => Action::Shift(10),
+                PushDir => Action::Shift(9),
+                Unset => Action::Shift(7),
+                Wait => Action::Shift(11),
+                Assignment => Action::Shift(16),
+                Word => Action::Shift(15),
                 _ => Action::SyntaxError,
             },
             22 => match aa_tag {
-                // Output: ">" Id #(NonAssoc, 0)
-                EAppend | EOverWrite | AAEnd => Action::Reduce(12),
+                // Args: <empty> #(NonAssoc, 0)
+                And | Append | Background | EAppend | EOverWrite | ErrToOut | Fixture | HereDoc
+                | HereString | InputFile | Kill | Or | OutToErr | OutToErr1 | OverWrite | Pipe
+                | PopDir | PushDir | Semicolon | Wait | Assignment | Word | AAEnd => {
+                    Action::Reduce(28)
+                }
                 _ => Action::SyntaxError,
             },
             23 => match aa_tag {
-                // Output: ">>" Id #(NonAssoc, 0)
-                EAppend | EOverWrite | AAEnd => Action::Reduce(13),
+                // Assignments: Assignments Assignment #(NonAssoc, 0)
+                And | Or | Semicolon | Assignment | Word | AAEnd => Action::Reduce(27),
                 _ => Action::SyntaxError,
             },
             24 => match aa_tag {
-                // ErrOutput: "2>" Id #(NonAssoc, 0)
-                AAEnd => Action::Reduce(15),
+                // Simple: "unset" Word #(NonAssoc, 0)
+                And | Or | Semicolon | AAEnd => Action::Reduce(10),
                 _ => Action::SyntaxError,
             },
             25 => match aa_tag {
-                // ErrOutput: "2>>" Id #(NonAssoc, 0)
-                AAEnd => Action::Reduce(16),
+                // Simple: "cd" Word #(NonAssoc, 0)
+                And | Or | Semicolon | AAEnd => Action::Reduce(12),
+                _ => Action::SyntaxError,
+            },
+            26 => match aa_tag {
+                // Simple: "pushd" Word #(NonAssoc, 0)
+                And | Or | Semicolon | AAEnd => Action::Reduce(13),
+                _ => Action::SyntaxError,
+            },
+            27 => match aa_tag {
+                // Simple: "wait" Word #(NonAssoc, 0)
+                And | Or | Semicolon | AAEnd => Action::Reduce(16),
+                _ => Action::SyntaxError,
+            },
+            28 => match aa_tag {
+                Word => Action::Shift(38),
+                // Simple: "kill" Word #(NonAssoc, 0)
+                And | Or | Semicolon | AAEnd => Action::Reduce(17),
+                _ => Action::SyntaxError,
+            },
+            29 => match aa_tag {
+                HereDoc => Action::Shift(39),
+                Word => Action::Shift(40),
+                _ => Action::SyntaxError,
+            },
+            30 => match aa_tag {
+                Fixture => Action::Shift(46),
+                HereDoc => Action::Shift(50),
+                HereString => Action::Shift(51),
+                InputFile => Action::Shift(49),
+                Kill => Action::Shift(48),
+                PopDir => Action::Shift(45),
+                PushDir => Action::Shift(44),
+                Wait => Action::Shift(47),
+                Assignment => Action::Shift(43),
+                Word => Action::Shift(42),
+                // Input: <empty> #(NonAssoc, 0)
+                And | Append | Background | EAppend | EOverWrite | ErrToOut | Or | OutToErr
+                | OutToErr1 | OverWrite | Pipe | Semicolon | AAEnd => Action::Reduce(36),
+                _ => Action::SyntaxError,
+            },
+            31 => match aa_tag {
+                And => Action::Shift(20),
+                Or => Action::Shift(21),
+                // List: List ";" AndOr #(NonAssoc, 0)
+                Semicolon | AAEnd => Action::Reduce(5),
+                _ => Action::SyntaxError,
+            },
+            32 => match aa_tag {
+                Pipe => Action::Shift(19),
+                // Simple: Pipeline #(NonAssoc, 0)
+                And | Or | Semicolon | AAEnd => Action::Reduce(21),
+                _ => Action::SyntaxError,
+            },
+            33 => match aa_tag {
+                // Pipeline: Pipeline "|" Program #(NonAssoc, 0)
+                And | Background | Or | Pipe | Semicolon | AAEnd => Action::Reduce(23),
+                _ => Action::SyntaxError,
+            },
+            34 => match aa_tag {
+                Assignment => Action::Shift(23),
+                Word => Action::Shift(22),
+                _ => Action::SyntaxError,
+            },
+            35 => match aa_tag {
+                // AndOr: AndOr "&&" Simple #(NonAssoc, 0)
+                And | Or | Semicolon | AAEnd => Action::Reduce(7),
+                _ => Action::SyntaxError,
+            },
+            36 => match aa_tag {
+                // AndOr: AndOr "||" Simple #(NonAssoc, 0)
+                And | Or | Semicolon | AAEnd => Action::Reduce(8),
+                _ => Action::SyntaxError,
+            },
+            37 => match aa_tag {
+                Fixture => Action::Shift(46),
+                HereDoc => Action::Shift(50),
+                HereString => Action::Shift(51),
+                InputFile => Action::Shift(49),
+                Kill => Action::Shift(48),
+                PopDir => Action::Shift(45),
+                PushDir => Action::Shift(44),
+                Wait => Action::Shift(47),
+                Assignment => Action::Shift(43),
+                Word => Action::Shift(42),
+                // Input: <empty> #(NonAssoc, 0)
+                And | Append | Background | EAppend | EOverWrite | ErrToOut | Or | OutToErr
+                | OutToErr1 | OverWrite | Pipe | Semicolon | AAEnd => Action::Reduce(36),
+                _ => Action::SyntaxError,
+            },
+            38 => match aa_tag {
+                // Simple: "kill" Word Word #(NonAssoc, 0)
+                And | Or | Semicolon | AAEnd => Action::Reduce(18),
+                _ => Action::SyntaxError,
+            },
+            39 => match aa_tag {
+                Word => Action::Shift(53),
+                _ => Action::SyntaxError,
+            },
+            40 => match aa_tag {
+                HereDoc => Action::Shift(54),
+                _ => Action::SyntaxError,
+            },
+            41 => match aa_tag {
+                Append => Action::Shift(57),
+                OutToErr => Action::Shift(58),
+                OutToErr1 => Action::Shift(59),
+                OverWrite => Action::Shift(56),
+                // Output: <empty> #(NonAssoc, 0)
+                And | Background | EAppend | EOverWrite | ErrToOut | Or | Pipe | Semicolon
+                | AAEnd => Action::Reduce(40),
+                _ => Action::SyntaxError,
+            },
+            42 => match aa_tag {
+                // Args: Args Word #(NonAssoc, 0)
+                And | Append | Background | EAppend | EOverWrite | ErrToOut | Fixture | HereDoc
+                | HereString | InputFile | Kill | Or | OutToErr | OutToErr1 | OverWrite | Pipe
+                | PopDir | PushDir | Semicolon | Wait | Assignment | Word | AAEnd => {
+                    Action::Reduce(29)
+                }
+                _ => Action::SyntaxError,
+            },
+            43 => match aa_tag {
+                // Args: Args Assignment #(NonAssoc, 0)
+                And | Append | Background | EAppend | EOverWrite | ErrToOut | Fixture | HereDoc
+                | HereString | InputFile | Kill | Or | OutToErr | OutToErr1 | OverWrite | Pipe
+                | PopDir | PushDir | Semicolon | Wait | Assignment | Word | AAEnd => {
+                    Action::Reduce(30)
+                }
+                _ => Action::SyntaxError,
+            },
+            44 => match aa_tag {
+                // Args: Args "pushd" #(NonAssoc, 0)
+                And | Append | Background | EAppend | EOverWrite | ErrToOut | Fixture | HereDoc
+                | HereString | InputFile | Kill | Or | OutToErr | OutToErr1 | OverWrite | Pipe
+                | PopDir | PushDir | Semicolon | Wait | Assignment | Word | AAEnd => {
+                    Action::Reduce(31)
+                }
+                _ => Action::SyntaxError,
+            },
+            45 => match aa_tag {
+                // Args: Args "popd" #(NonAssoc, 0)
+                And | Append | Background | EAppend | EOverWrite | ErrToOut | Fixture | HereDoc
+                | HereString | InputFile | Kill | Or | OutToErr | OutToErr1 | OverWrite | Pipe
+                | PopDir | PushDir | Semicolon | Wait | Assignment | Word | AAEnd => {
+                    Action::Reduce(32)
+                }
+                _ => Action::SyntaxError,
+            },
+            46 => match aa_tag {
+                // Args: Args "fixture" #(NonAssoc, 0)
+                And | Append | Background | EAppend | EOverWrite | ErrToOut | Fixture | HereDoc
+                | HereString | InputFile | Kill | Or | OutToErr | OutToErr1 | OverWrite | Pipe
+                | PopDir | PushDir | Semicolon | Wait | Assignment | Word | AAEnd => {
+                    Action::Reduce(33)
+                }
+                _ => Action::SyntaxError,
+            },
+            47 => match aa_tag {
+                // Args: Args "wait" #(NonAssoc, 0)
+                And | Append | Background | EAppend | EOverWrite | ErrToOut | Fixture | HereDoc
+                | HereString | InputFile | Kill | Or | OutToErr | OutToErr1 | OverWrite | Pipe
+                | PopDir | PushDir | Semicolon | Wait | Assignment | Word | AAEnd => {
+                    Action::Reduce(34)
+                }
+                _ => Action::SyntaxError,
+            },
+            48 => match aa_tag {
+                // Args: Args "kill" #(NonAssoc, 0)
+                And | Append | Background | EAppend | EOverWrite | ErrToOut | Fixture | HereDoc
+                | HereString | InputFile | Kill | Or | OutToErr | OutToErr1 | OverWrite | Pipe
+                | PopDir | PushDir | Semicolon | Wait | Assignment | Word | AAEnd => {
+                    Action::Reduce(35)
+                }
+                _ => Action::SyntaxError,
+            },
+            49 => match aa_tag {
+                Word => Action::Shift(60),
+                _ => Action::SyntaxError,
+            },
+            50 => match aa_tag {
+                Word => Action::Shift(61),
+                _ => Action::SyntaxError,
+            },
+            51 => match aa_tag {
+                Word => Action::Shift(62),
+                _ => Action::SyntaxError,
+            },
+            52 => match aa_tag {
+                Append => Action::Shift(57),
+                OutToErr => Action::Shift(58),
+                OutToErr1 => Action::Shift(59),
+                OverWrite => Action::Shift(56),
+                // Output: <empty> #(NonAssoc, 0)
+                And | Background | EAppend | EOverWrite | ErrToOut | Or | Pipe | Semicolon
+                | AAEnd => Action::Reduce(40),
+                _ => Action::SyntaxError,
+            },
+            53 => match aa_tag {
+                // Simple: "fixture" Word "<<" Word #(NonAssoc, 0)
+                And | Or | Semicolon | AAEnd => Action::Reduce(19),
+                _ => Action::SyntaxError,
+            },
+            54 => match aa_tag {
+                Word => Action::Shift(64),
+                _ => Action::SyntaxError,
+            },
+            55 => match aa_tag {
+                EAppend => Action::Shift(67),
+                EOverWrite => Action::Shift(66),
+                ErrToOut => Action::Shift(68),
+                // ErrOutput: <empty> #(NonAssoc, 0)
+                And | Background | Or | Pipe | Semicolon | AAEnd => Action::Reduce(45),
+                _ => Action::SyntaxError,
+            },
+            56 => match aa_tag {
+                Word => Action::Shift(69),
+                _ => Action::SyntaxError,
+            },
+            57 => match aa_tag {
+                Word => Action::Shift(70),
+                _ => Action::SyntaxError,
+            },
+            58 => match aa_tag {
+                // Output: ">&2" #(NonAssoc, 0)
+                And | Background | EAppend | EOverWrite | ErrToOut | Or | Pipe | Semicolon
+                | AAEnd => Action::Reduce(43),
+                _ => Action::SyntaxError,
+            },
+            59 => match aa_tag {
+                // Output: "1>&2" #(NonAssoc, 0)
+                And | Background | EAppend | EOverWrite | ErrToOut | Or | Pipe | Semicolon
+                | AAEnd => Action::Reduce(44),
+                _ => Action::SyntaxError,
+            },
+            60 => match aa_tag {
+                // Input: "<" Word #(NonAssoc, 0)
+                And | Append | Background | EAppend | EOverWrite | ErrToOut | Or | OutToErr
+                | OutToErr1 | OverWrite | Pipe | Semicolon | AAEnd => Action::Reduce(37),
+                _ => Action::SyntaxError,
+            },
+            61 => match aa_tag {
+                // Input: "<<" Word #(NonAssoc, 0)
+                And | Append | Background | EAppend | EOverWrite | ErrToOut | Or | OutToErr
+                | OutToErr1 | OverWrite | Pipe | Semicolon | AAEnd => Action::Reduce(38),
+                _ => Action::SyntaxError,
+            },
+            62 => match aa_tag {
+                // Input: "<<<" Word #(NonAssoc, 0)
+                And | Append | Background | EAppend | EOverWrite | ErrToOut | Or | OutToErr
+                | OutToErr1 | OverWrite | Pipe | Semicolon | AAEnd => Action::Reduce(39),
+                _ => Action::SyntaxError,
+            },
+            63 => match aa_tag {
+                EAppend => Action::Shift(67),
+                EOverWrite => Action::Shift(66),
+                ErrToOut => Action::Shift(68),
+                // ErrOutput: <empty> #(NonAssoc, 0)
+                And | Background | Or | Pipe | Semicolon | AAEnd => Action::Reduce(45),
+                _ => Action::SyntaxError,
+            },
+            64 => match aa_tag {
+                // Simple: "fixture" Word Word "<<" Word #(NonAssoc, 0)
+                And | Or | Semicolon | AAEnd => Action::Reduce(20),
+                _ => Action::SyntaxError,
+            },
+            65 => match aa_tag {
+                // Program: Word Args Input Output ErrOutput #(NonAssoc, 0)
+                And | Background | Or | Pipe | Semicolon | AAEnd => Action::Reduce(24),
+                _ => Action::SyntaxError,
+            },
+            66 => match aa_tag {
+                Word => Action::Shift(72),
+                _ => Action::SyntaxError,
+            },
+            67 => match aa_tag {
+                Word => Action::Shift(73),
+                _ => Action::SyntaxError,
+            },
+            68 => match aa_tag {
+                // ErrOutput: "2>&1" #(NonAssoc, 0)
+                And | Background | Or | Pipe | Semicolon | AAEnd => Action::Reduce(48),
+                _ => Action::SyntaxError,
+            },
+            69 => match aa_tag {
+                // Output: ">" Word #(NonAssoc, 0)
+                And | Background | EAppend | EOverWrite | ErrToOut | Or | Pipe | Semicolon
+                | AAEnd => Action::Reduce(41),
+                _ => Action::SyntaxError,
+            },
+            70 => match aa_tag {
+                // Output: ">>" Word #(NonAssoc, 0)
+                And | Background | EAppend | EOverWrite | ErrToOut | Or | Pipe | Semicolon
+                | AAEnd => Action::Reduce(42),
+                _ => Action::SyntaxError,
+            },
+            71 => match aa_tag {
+                // Program: Assignments Word Args Input Output ErrOutput #(NonAssoc, 0)
+                And | Background | Or | Pipe | Semicolon | AAEnd => Action::Reduce(25),
+                _ => Action::SyntaxError,
+            },
+            72 => match aa_tag {
+                // ErrOutput: "2>" Word #(NonAssoc, 0)
+                And | Background | Or | Pipe | Semicolon | AAEnd => Action::Reduce(46),
+                _ => Action::SyntaxError,
+            },
+            73 => match aa_tag {
+                // ErrOutput: "2>>" Word #(NonAssoc, 0)
+                And | Background | Or | Pipe | Semicolon | AAEnd => Action::Reduce(47),
                 _ => Action::SyntaxError,
             },
             _ => panic!("illegal state: {aa_state}"),
@@ -397,22 +996,54 @@ impl lalr1::Parser<AATerminal, AANonTerminal, AttributeData> for CommandAction {
     fn production_data(production_id: u32) -> (AANonTerminal, usize) {
         match production_id {
             0 => (AANonTerminal::AAStart, 1),
-            1 => (AANonTerminal::CommandAction, 3),
-            2 => (AANonTerminal::CommandAction, 3),
+            1 => (AANonTerminal::CommandAction, 1),
+            2 => (AANonTerminal::CommandAction, 2),
             3 => (AANonTerminal::CommandAction, 2),
-            4 => (AANonTerminal::CommandAction, 2),
-            5 => (AANonTerminal::CommandAction, 5),
-            6 => (AANonTerminal::Args, 0),
-            7 => (AANonTerminal::Args, 2),
-            8 => (AANonTerminal::Args, 2),
-            9 => (AANonTerminal::Input, 0),
-            10 => (AANonTerminal::Input, 2),
-            11 => (AANonTerminal::Output, 0),
-            12 => (AANonTerminal::Output, 2),
-            13 => (AANonTerminal::Output, 2),
-            14 => (AANonTerminal::ErrOutput, 0),
-            15 => (AANonTerminal::ErrOutput, 2),
-            16 => (AANonTerminal::ErrOutput, 2),
+            4 => (AANonTerminal::List, 1),
+            5 => (AANonTerminal::List, 3),
+            6 => (AANonTerminal::AndOr, 1),
+            7 => (AANonTerminal::AndOr, 3),
+            8 => (AANonTerminal::AndOr, 3),
+            9 => (AANonTerminal::Simple, 1),
+            10 => (AANonTerminal::Simple, 2),
+            11 => (AANonTerminal::Simple, 1),
+            12 => (AANonTerminal::Simple, 2),
+            13 => (AANonTerminal::Simple, 2),
+            14 => (AANonTerminal::Simple, 1),
+            15 => (AANonTerminal::Simple, 1),
+            16 => (AANonTerminal::Simple, 2),
+            17 => (AANonTerminal::Simple, 2),
+            18 => (AANonTerminal::Simple, 3),
+            19 => (AANonTerminal::Simple, 4),
+            20 => (AANonTerminal::Simple, 5),
+            21 => (AANonTerminal::Simple, 1),
+            22 => (AANonTerminal::Pipeline, 1),
+            23 => (AANonTerminal::Pipeline, 3),
+            24 => (AANonTerminal::Program, 5),
+            25 => (AANonTerminal::Program, 6),
+            26 => (AANonTerminal::Assignments, 1),
+            27 => (AANonTerminal::Assignments, 2),
+            28 => (AANonTerminal::Args, 0),
+            29 => (AANonTerminal::Args, 2),
+            30 => (AANonTerminal::Args, 2),
+            31 => (AANonTerminal::Args, 2),
+            32 => (AANonTerminal::Args, 2),
+            33 => (AANonTerminal::Args, 2),
+            34 => (AANonTerminal::Args, 2),
+            35 => (AANonTerminal::Args, 2),
+            36 => (AANonTerminal::Input, 0),
+            37 => (AANonTerminal::Input, 2),
+            38 => (AANonTerminal::Input, 2),
+            39 => (AANonTerminal::Input, 2),
+            40 => (AANonTerminal::Output, 0),
+            41 => (AANonTerminal::Output, 2),
+            42 => (AANonTerminal::Output, 2),
+            43 => (AANonTerminal::Output, 1),
+            44 => (AANonTerminal::Output, 1),
+            45 => (AANonTerminal::ErrOutput, 0),
+            46 => (AANonTerminal::ErrOutput, 2),
+            47 => (AANonTerminal::ErrOutput, 2),
+            48 => (AANonTerminal::ErrOutput, 1),
             _ => panic!("malformed production data table"),
         }
     }
@@ -420,23 +1051,72 @@ impl lalr1::Parser<AATerminal, AANonTerminal, AttributeData> for CommandAction {
     fn goto_state(lhs: &AANonTerminal, current_state: u32) -> u32 {
         match current_state {
             0 => match lhs {
+                AANonTerminal::AndOr => 4,
+                AANonTerminal::Assignments => 6,
                 AANonTerminal::CommandAction => 1,
+                AANonTerminal::List => 2,
+                AANonTerminal::Pipeline => 3,
+                AANonTerminal::Program => 14,
+                AANonTerminal::Simple => 5,
                 _ => panic!("Malformed goto table: ({lhs}, {current_state})"),
             },
-            2 => match lhs {
-                AANonTerminal::Args => 6,
+            15 => match lhs {
+                AANonTerminal::Args => 30,
                 _ => panic!("Malformed goto table: ({lhs}, {current_state})"),
             },
-            6 => match lhs {
-                AANonTerminal::Input => 11,
+            17 => match lhs {
+                AANonTerminal::AndOr => 31,
+                AANonTerminal::Assignments => 6,
+                AANonTerminal::Pipeline => 32,
+                AANonTerminal::Program => 14,
+                AANonTerminal::Simple => 5,
                 _ => panic!("Malformed goto table: ({lhs}, {current_state})"),
             },
-            11 => match lhs {
-                AANonTerminal::Output => 15,
+            19 => match lhs {
+                AANonTerminal::Assignments => 34,
+                AANonTerminal::Program => 33,
                 _ => panic!("Malformed goto table: ({lhs}, {current_state})"),
             },
-            15 => match lhs {
-                AANonTerminal::ErrOutput => 19,
+            20 => match lhs {
+                AANonTerminal::Assignments => 6,
+                AANonTerminal::Pipeline => 32,
+                AANonTerminal::Program => 14,
+                AANonTerminal::Simple => 35,
+                _ => panic!("Malformed goto table: ({lhs}, {current_state})"),
+            },
+            21 => match lhs {
+                AANonTerminal::Assignments => 6,
+                AANonTerminal::Pipeline => 32,
+                AANonTerminal::Program => 14,
+                AANonTerminal::Simple => 36,
+                _ => panic!("Malformed goto table: ({lhs}, {current_state})"),
+            },
+            22 => match lhs {
+                AANonTerminal::Args => 37,
+                _ => panic!("Malformed goto table: ({lhs}, {current_state})"),
+            },
+            30 => match lhs {
+                AANonTerminal::Input => 41,
+                _ => panic!("Malformed goto table: ({lhs}, {current_state})"),
+            },
+            37 => match lhs {
+                AANonTerminal::Input => 52,
+                _ => panic!("Malformed goto table: ({lhs}, {current_state})"),
+            },
+            41 => match lhs {
+                AANonTerminal::Output => 55,
+                _ => panic!("Malformed goto table: ({lhs}, {current_state})"),
+            },
+            52 => match lhs {
+                AANonTerminal::Output => 63,
+                _ => panic!("Malformed goto table: ({lhs}, {current_state})"),
+            },
+            55 => match lhs {
+                AANonTerminal::ErrOutput => 65,
+                _ => panic!("Malformed goto table: ({lhs}, {current_state})"),
+            },
+            63 => match lhs {
+                AANonTerminal::ErrOutput => 71,
                 _ => panic!("Malformed goto table: ({lhs}, {current_state})"),
             },
             _ => panic!("Malformed goto table: ({lhs}, {current_state})"),
@@ -456,82 +1136,273 @@ impl lalr1::Parser<AATerminal, AANonTerminal, AttributeData> for CommandAction {
         };
         match aa_production_id {
             1 => {
-                // CommandAction: Id "=" Id #(NonAssoc, 0)
+                // CommandAction: List #(NonAssoc, 0)
 
-                *self = CommandAction::SetEnvVar(aa_rhs[0].id(), aa_rhs[2].id());
+                *self = aa_rhs[0].action();
             }
             2 => {
-                // CommandAction: Id "=" String #(NonAssoc, 0)
+                // CommandAction: List ";" #(NonAssoc, 0)
 
-                *self = CommandAction::SetEnvVar(aa_rhs[0].id(), aa_rhs[2].string());
+                *self = aa_rhs[0].action();
             }
             3 => {
-                // CommandAction: "unset" Id #(NonAssoc, 0)
+                // CommandAction: Pipeline "&" #(NonAssoc, 0)
 
-                *self = CommandAction::UnsetEnvVar(aa_rhs[1].id());
+                *self = CommandAction::Background(aa_rhs[0].stages());
             }
-            4 => {
-                // CommandAction: "cd" Id #(NonAssoc, 0)
+            5 => {
+                // List: List ";" AndOr #(NonAssoc, 0)
 
-                *self = CommandAction::ChangeDir(aa_rhs[1].id());
+                aa_lhs = AttributeData::Action(CommandAction::Sequence(
+                    Box::new(aa_rhs[0].action()),
+                    Box::new(aa_rhs[2].action()),
+                ));
             }
-            5 => {
-                // CommandAction: Id Args Input Output ErrOutput #(NonAssoc, 0)
+            7 => {
+                // AndOr: AndOr "&&" Simple #(NonAssoc, 0)
+
+                aa_lhs = AttributeData::Action(CommandAction::AndList(
+                    Box::new(aa_rhs[0].action()),
+                    Box::new(aa_rhs[2].action()),
+                ));
+            }
+            8 => {
+                // AndOr: AndOr "||" Simple #(NonAssoc, 0)
 
-                *self = CommandAction::RunProgram(
-                    aa_rhs[0].id(),
+                aa_lhs = AttributeData::Action(CommandAction::OrList(
+                    Box::new(aa_rhs[0].action()),
+                    Box::new(aa_rhs[2].action()),
+                ));
+            }
+            9 => {
+                // Simple: Assignments #(NonAssoc, 0)
+
+                // as in a shell, the assignments are made in order
+                let mut assignments = aa_rhs[0].assignments().into_iter();
+                let (name, value) = assignments.next().expect("at least one assignment");
+                let mut action = CommandAction::SetEnvVar(name, value);
+                for (name, value) in assignments {
+                    action = CommandAction::Sequence(
+                        Box::new(action),
+                        Box::new(CommandAction::SetEnvVar(name, value)),
+                    );
+                }
+                aa_lhs = AttributeData::Action(action);
+            }
+            10 => {
+                // Simple: "unset" Word #(NonAssoc, 0)
+
+                aa_lhs = AttributeData::Action(CommandAction::UnsetEnvVar(aa_rhs[1].word()));
+            }
+            11 => {
+                // Simple: "cd" #(NonAssoc, 0)
+
+                aa_lhs = AttributeData::Action(CommandAction::ChangeDir(None));
+            }
+            12 => {
+                // Simple: "cd" Word #(NonAssoc, 0)
+
+                aa_lhs = AttributeData::Action(CommandAction::ChangeDir(Some(aa_rhs[1].word())));
+            }
+            13 => {
+                // Simple: "pushd" Word #(NonAssoc, 0)
+
+                aa_lhs = AttributeData::Action(CommandAction::PushDir(aa_rhs[1].word()));
+            }
+            14 => {
+                // Simple: "popd" #(NonAssoc, 0)
+
+                aa_lhs = AttributeData::Action(CommandAction::PopDir);
+            }
+            15 => {
+                // Simple: "wait" #(NonAssoc, 0)
+
+                aa_lhs = AttributeData::Action(CommandAction::Wait(None));
+            }
+            16 => {
+                // Simple: "wait" Word #(NonAssoc, 0)
+
+                aa_lhs = AttributeData::Action(CommandAction::Wait(Some(aa_rhs[1].word())));
+            }
+            17 => {
+                // Simple: "kill" Word #(NonAssoc, 0)
+
+                aa_lhs = AttributeData::Action(CommandAction::Kill(None, aa_rhs[1].word()));
+            }
+            18 => {
+                // Simple: "kill" Word Word #(NonAssoc, 0)
+
+                aa_lhs = AttributeData::Action(CommandAction::Kill(
+                    Some(aa_rhs[1].word()),
+                    aa_rhs[2].word(),
+                ));
+            }
+            19 => {
+                // Simple: "fixture" Word "<<" Word #(NonAssoc, 0)
+
+                aa_lhs = AttributeData::Action(CommandAction::CreateFile(
+                    aa_rhs[1].word(),
+                    None,
+                    aa_rhs[3].word(),
+                    String::new(),
+                ));
+            }
+            20 => {
+                // Simple: "fixture" Word Word "<<" Word #(NonAssoc, 0)
+
+                aa_lhs = AttributeData::Action(CommandAction::CreateFile(
+                    aa_rhs[1].word(),
+                    Some(aa_rhs[2].word()),
+                    aa_rhs[4].word(),
+                    String::new(),
+                ));
+            }
+            21 => {
+                // Simple: Pipeline #(NonAssoc, 0)
+
+                let mut stages = aa_rhs[0].stages();
+                if stages.len() == 1 {
+                    aa_lhs = AttributeData::Action(stages.remove(0));
+                } else {
+                    aa_lhs = AttributeData::Action(CommandAction::Pipeline(stages));
+                }
+            }
+            22 => {
+                // Pipeline: Program #(NonAssoc, 0)
+
+                aa_lhs = AttributeData::Stages(vec![aa_rhs[0].action()]);
+            }
+            23 => {
+                // Pipeline: Pipeline "|" Program #(NonAssoc, 0)
+
+                aa_lhs.stages_mut().push(aa_rhs[2].action());
+            }
+            24 => {
+                // Program: Word Args Input Output ErrOutput #(NonAssoc, 0)
+
+                aa_lhs = AttributeData::Action(CommandAction::RunProgram(
+                    vec![],
+                    aa_rhs[0].word(),
                     aa_rhs[1].args(),
                     aa_rhs[2].input(),
                     aa_rhs[3].output(),
                     aa_rhs[4].output(),
-                );
+                ));
+            }
+            25 => {
+                // Program: Assignments Word Args Input Output ErrOutput #(NonAssoc, 0)
+
+                aa_lhs = AttributeData::Action(CommandAction::RunProgram(
+                    aa_rhs[0].assignments(),
+                    aa_rhs[1].word(),
+                    aa_rhs[2].args(),
+                    aa_rhs[3].input(),
+                    aa_rhs[4].output(),
+                    aa_rhs[5].output(),
+                ));
+            }
+            26 => {
+                // Assignments: Assignment #(NonAssoc, 0)
+
+                aa_lhs = AttributeData::Assignments(vec![aa_rhs[0].assignment()]);
+            }
+            27 => {
+                // Assignments: Assignments Assignment #(NonAssoc, 0)
+
+                aa_lhs.assignments_mut().push(aa_rhs[1].assignment());
             }
-            6 => {
+            28 => {
                 // Args: <empty> #(NonAssoc, 0)
 
                 aa_lhs = AttributeData::Args(vec![]);
             }
-            7 => {
-                // Args: Args Id #(NonAssoc, 0)
+            29 => {
+                // Args: Args Word #(NonAssoc, 0)
 
-                aa_lhs.args_mut().push(aa_rhs[1].id())
+                aa_lhs.args_mut().push(aa_rhs[1].word())
             }
-            8 => {
-                // Args: Args String #(NonAssoc, 0)
+            30 => {
+                // Args: Args Assignment #(NonAssoc, 0)
 
-                aa_lhs.args_mut().push(aa_rhs[1].string())
+                aa_lhs.args_mut().push(aa_rhs[1].word())
             }
-            9 => {
+            31 => {
+                // Args: Args "pushd" #(NonAssoc, 0)
+
+                aa_lhs.args_mut().push(aa_rhs[1].word())
+            }
+            32 => {
+                // Args: Args "popd" #(NonAssoc, 0)
+
+                aa_lhs.args_mut().push(aa_rhs[1].word())
+            }
+            33 => {
+                // Args: Args "fixture" #(NonAssoc, 0)
+
+                aa_lhs.args_mut().push(aa_rhs[1].word())
+            }
+            34 => {
+                // Args: Args "wait" #(NonAssoc, 0)
+
+                aa_lhs.args_mut().push(aa_rhs[1].word())
+            }
+            35 => {
+                // Args: Args "kill" #(NonAssoc, 0)
+
+                aa_lhs.args_mut().push(aa_rhs[1].word())
+            }
+            36 => {
                 // Input: <empty> #(NonAssoc, 0)
                 aa_lhs = AttributeData::Input(None);
             }
-            10 => {
-                // Input: "<" Id #(NonAssoc, 0)
-                aa_lhs = AttributeData::Input(Some(aa_rhs[1].id()));
+            37 => {
+                // Input: "<" Word #(NonAssoc, 0)
+                aa_lhs = AttributeData::Input(Some(Input::File(aa_rhs[1].word())));
             }
-            11 => {
+            38 => {
+                // Input: "<<" Word #(NonAssoc, 0)
+                aa_lhs =
+                    AttributeData::Input(Some(Input::HereDoc(aa_rhs[1].word(), String::new())));
+            }
+            39 => {
+                // Input: "<<<" Word #(NonAssoc, 0)
+                aa_lhs = AttributeData::Input(Some(Input::HereString(aa_rhs[1].word())));
+            }
+            40 => {
                 // Output: <empty> #(NonAssoc, 0)
                 aa_lhs = AttributeData::Output(None);
             }
-            12 => {
-                // Output: ">" Id #(NonAssoc, 0)
-                aa_lhs = AttributeData::Output(Some((aa_rhs[1].id(), true)));
+            41 => {
+                // Output: ">" Word #(NonAssoc, 0)
+                aa_lhs = AttributeData::Output(Some(Output::File(aa_rhs[1].word(), true)));
             }
-            13 => {
-                // Output: ">>" Id #(NonAssoc, 0)
-                aa_lhs = AttributeData::Output(Some((aa_rhs[1].id(), false)));
+            42 => {
+                // Output: ">>" Word #(NonAssoc, 0)
+                aa_lhs = AttributeData::Output(Some(Output::File(aa_rhs[1].word(), false)));
             }
-            14 => {
+            43 => {
+                // Output: ">&2" #(NonAssoc, 0)
+                aa_lhs = AttributeData::Output(Some(Output::StdErr));
+            }
+            44 => {
+                // Output: "1>&2" #(NonAssoc, 0)
+                aa_lhs = AttributeData::Output(Some(Output::StdErr));
+            }
+            45 => {
                 // ErrOutput: <empty> #(NonAssoc, 0)
                 aa_lhs = AttributeData::Output(None);
             }
-            15 => {
-                // ErrOutput: "2>" Id #(NonAssoc, 0)
-                aa_lhs = AttributeData::Output(Some((aa_rhs[1].id(), true)));
+            46 => {
+                // ErrOutput: "2>" Word #(NonAssoc, 0)
+                aa_lhs = AttributeData::Output(Some(Output::File(aa_rhs[1].word(), true)));
             }
-            16 => {
-                // ErrOutput: "2>>" Id #(NonAssoc, 0)
-                aa_lhs = AttributeData::Output(Some((aa_rhs[1].id(), false)));
+            47 => {
+                // ErrOutput: "2>>" Word #(NonAssoc, 0)
+                aa_lhs = AttributeData::Output(Some(Output::File(aa_rhs[1].word(), false)));
+            }
+            48 => {
+                // ErrOutput: "2>&1" #(NonAssoc, 0)
+                aa_lhs = AttributeData::Output(Some(Output::StdOut));
             }
             _ => aa_inject(String::new(), String::new()),
         };
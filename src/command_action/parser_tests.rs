@@ -14,18 +14,22 @@ fn test_command_parse() {
     assert_eq!(action, CommandAction::UnsetEnvVar("WHATEVER".to_string()));
 
     assert!(action.parse_text("cd WHATEVER", "label").is_ok());
-    assert_eq!(action, CommandAction::ChangeDir("WHATEVER".to_string()));
+    assert_eq!(
+        action,
+        CommandAction::ChangeDir(Some("WHATEVER".to_string()))
+    );
 
     assert!(action.parse_text("ls", "label").is_ok());
     assert_eq!(
         action,
-        CommandAction::RunProgram("ls".to_string(), vec![], None, None, None)
+        CommandAction::RunProgram(vec![], "ls".to_string(), vec![], None, None, None)
     );
 
     assert!(action.parse_text("echo hello world", "label").is_ok());
     assert_eq!(
         action,
         CommandAction::RunProgram(
+            vec![],
             "echo".to_string(),
             vec!["hello".to_string(), "world".to_string()],
             None,
@@ -40,10 +44,11 @@ fn test_command_parse() {
     assert_eq!(
         action,
         CommandAction::RunProgram(
+            vec![],
             "echo".to_string(),
             vec!["hello".to_string(), "world".to_string()],
-            Some("something".to_string()),
-            Some(("else".to_string(), true)),
+            Some(Input::File("something".to_string())),
+            Some(Output::File("else".to_string(), true)),
             None
         )
     );
@@ -54,11 +59,561 @@ fn test_command_parse() {
     assert_eq!(
         action,
         CommandAction::RunProgram(
+            vec![],
             "echo".to_string(),
             vec!["hello".to_string(), "world".to_string()],
-            Some("something".to_string()),
-            Some(("else".to_string(), false)),
-            Some(("error".to_string(), true))
+            Some(Input::File("something".to_string())),
+            Some(Output::File("else".to_string(), false)),
+            Some(Output::File("error".to_string(), true))
+        )
+    );
+}
+
+#[test]
+fn test_pipeline_parse() {
+    let mut action = CommandAction::default();
+    assert!(action
+        .parse_text("ls -a | sort -r > sorted", "label")
+        .is_ok());
+    assert_eq!(
+        action,
+        CommandAction::Pipeline(vec![
+            CommandAction::RunProgram(
+                vec![],
+                "ls".to_string(),
+                vec!["-a".to_string()],
+                None,
+                None,
+                None
+            ),
+            CommandAction::RunProgram(
+                vec![],
+                "sort".to_string(),
+                vec!["-r".to_string()],
+                None,
+                Some(Output::File("sorted".to_string(), true)),
+                None
+            ),
+        ])
+    );
+
+    assert!(action
+        .parse_text("cat < input | sort | uniq 2> errors", "label")
+        .is_ok());
+    match &action {
+        CommandAction::Pipeline(stages) => assert_eq!(stages.len(), 3),
+        _ => panic!("expected Pipeline"),
+    }
+
+    assert!(action.parse_text("ls |", "label").is_err());
+    assert!(action.parse_text("| ls", "label").is_err());
+}
+
+#[test]
+fn test_list_parse() {
+    use CommandAction::*;
+    let mut action = CommandAction::default();
+    assert!(action.parse_text("mkdir out && cd out", "label").is_ok());
+    assert_eq!(
+        action,
+        AndList(
+            Box::new(RunProgram(
+                vec![],
+                "mkdir".to_string(),
+                vec!["out".to_string()],
+                None,
+                None,
+                None
+            )),
+            Box::new(ChangeDir(Some("out".to_string())))
+        )
+    );
+
+    assert!(action.parse_text("false || X=y; unset X;", "label").is_ok());
+    assert_eq!(
+        action,
+        Sequence(
+            Box::new(OrList(
+                Box::new(RunProgram(
+                    vec![],
+                    "false".to_string(),
+                    vec![],
+                    None,
+                    None,
+                    None
+                )),
+                Box::new(SetEnvVar("X".to_string(), "y".to_string()))
+            )),
+            Box::new(UnsetEnvVar("X".to_string()))
+        )
+    );
+
+    assert!(action
+        .parse_text("true && ls | sort || false", "label")
+        .is_ok());
+    match &action {
+        OrList(first, _) => match first.as_ref() {
+            AndList(_, second) => assert!(matches!(second.as_ref(), Pipeline(_))),
+            _ => panic!("expected AndList"),
+        },
+        _ => panic!("expected OrList"),
+    }
+
+    assert!(action.parse_text("&& ls", "label").is_err());
+    assert!(action.parse_text("ls ;;", "label").is_err());
+}
+
+#[test]
+fn test_variable_parse() {
+    use CommandAction::*;
+    let mut action = CommandAction::default();
+    assert!(action.parse_text("cd $HOME/${SUB:-dir x}", "label").is_ok());
+    assert_eq!(action, ChangeDir(Some("$HOME/${SUB:-dir x}".to_string())));
+
+    assert!(action
+        .parse_text("tool --config $HOME/cfg < ${IN}", "label")
+        .is_ok());
+    assert_eq!(
+        action,
+        RunProgram(
+            vec![],
+            "tool".to_string(),
+            vec!["--config".to_string(), "$HOME/cfg".to_string()],
+            Some(Input::File("${IN}".to_string())),
+            None,
+            None
+        )
+    );
+
+    assert!(action
+        .parse_text(
+            r#"echo $(echo "(x)") $(echo ')') $(echo $(date))x"#,
+            "label"
+        )
+        .is_ok());
+    assert_eq!(
+        action,
+        RunProgram(
+            vec![],
+            "echo".to_string(),
+            vec![
+                r#"$(echo "(x)")"#.to_string(),
+                "$(echo ')')".to_string(),
+                "$(echo $(date))x".to_string()
+            ],
+            None,
+            None,
+            None
+        )
+    );
+    assert!(action
+        .parse_text("echo $(echo $(echo $(date)))", "label")
+        .is_err());
+}
+
+#[test]
+fn test_quoting_parse() {
+    use CommandAction::*;
+    let mut action = CommandAction::default();
+    assert!(action
+        .parse_text(
+            r#"tool --name="a b" 'c d'\ e x=1 "f|g" > out\ file"#,
+            "label"
+        )
+        .is_ok());
+    assert_eq!(
+        action,
+        RunProgram(
+            vec![],
+            "tool".to_string(),
+            vec![
+                r#"--name="a b""#.to_string(),
+                r#"'c d'\ e"#.to_string(),
+                "x=1".to_string(),
+                r#""f|g""#.to_string()
+            ],
+            None,
+            Some(Output::File(r#"out\ file"#.to_string(), true)),
+            None
+        )
+    );
+
+    assert!(action.parse_text(r#"MSG="hello world""#, "label").is_ok());
+    assert_eq!(
+        action,
+        SetEnvVar("MSG".to_string(), r#""hello world""#.to_string())
+    );
+
+    assert!(action.parse_text("NAME='a b'c", "label").is_ok());
+    assert_eq!(action, SetEnvVar("NAME".to_string(), "'a b'c".to_string()));
+
+    assert!(action.parse_text(r#"echo "abc"#, "label").is_err());
+}
+
+#[test]
+fn test_here_doc_parse() {
+    use CommandAction::*;
+    let mut action = CommandAction::default();
+    assert!(action.parse_text("cat <<EOF > out", "label").is_ok());
+    assert_eq!(
+        action,
+        RunProgram(
+            vec![],
+            "cat".to_string(),
+            vec![],
+            Some(Input::HereDoc("EOF".to_string(), String::new())),
+            Some(Output::File("out".to_string(), true)),
+            None
+        )
+    );
+
+    assert!(action.parse_text("cat <<'EOF' | wc -l", "label").is_ok());
+    assert_eq!(
+        action,
+        Pipeline(vec![
+            RunProgram(
+                vec![],
+                "cat".to_string(),
+                vec![],
+                Some(Input::HereDoc("'EOF'".to_string(), String::new())),
+                None,
+                None
+            ),
+            RunProgram(
+                vec![],
+                "wc".to_string(),
+                vec!["-l".to_string()],
+                None,
+                None,
+                None
+            ),
+        ])
+    );
+
+    assert!(action.parse_text(r#"cat <<< "a $B""#, "label").is_ok());
+    assert_eq!(
+        action,
+        RunProgram(
+            vec![],
+            "cat".to_string(),
+            vec![],
+            Some(Input::HereString(r#""a $B""#.to_string())),
+            None,
+            None
+        )
+    );
+
+    assert!(action.parse_text("cat <<", "label").is_err());
+}
+
+#[test]
+fn test_merge_output_parse() {
+    use CommandAction::*;
+    let mut action = CommandAction::default();
+    assert!(action.parse_text("make 2>&1", "label").is_ok());
+    assert_eq!(
+        action,
+        RunProgram(
+            vec![],
+            "make".to_string(),
+            vec![],
+            None,
+            None,
+            Some(Output::StdOut)
+        )
+    );
+
+    assert!(action.parse_text("make > log 2>&1", "label").is_ok());
+    assert_eq!(
+        action,
+        RunProgram(
+            vec![],
+            "make".to_string(),
+            vec![],
+            None,
+            Some(Output::File("log".to_string(), true)),
+            Some(Output::StdOut)
+        )
+    );
+
+    assert!(action.parse_text("echo oops >&2", "label").is_ok());
+    assert_eq!(
+        action,
+        RunProgram(
+            vec![],
+            "echo".to_string(),
+            vec!["oops".to_string()],
+            None,
+            Some(Output::StdErr),
+            None
+        )
+    );
+
+    assert!(action
+        .parse_text("echo oops 1>&2 2>> errors", "label")
+        .is_ok());
+    assert_eq!(
+        action,
+        RunProgram(
+            vec![],
+            "echo".to_string(),
+            vec!["oops".to_string()],
+            None,
+            Some(Output::StdErr),
+            Some(Output::File("errors".to_string(), false))
+        )
+    );
+
+    assert!(action.parse_text("echo 2>&1 >&2", "label").is_err());
+}
+
+#[test]
+fn test_program_env_parse() {
+    use CommandAction::*;
+    let mut action = CommandAction::default();
+    assert!(action
+        .parse_text("RUST_LOG=debug X='a b' mytool run X=y", "label")
+        .is_ok());
+    assert_eq!(
+        action,
+        RunProgram(
+            vec![
+                ("RUST_LOG".to_string(), "debug".to_string()),
+                ("X".to_string(), "'a b'".to_string())
+            ],
+            "mytool".to_string(),
+            vec!["run".to_string(), "X=y".to_string()],
+            None,
+            None,
+            None
+        )
+    );
+
+    assert!(action.parse_text("A=1 B=2", "label").is_ok());
+    assert_eq!(
+        action,
+        Sequence(
+            Box::new(SetEnvVar("A".to_string(), "1".to_string())),
+            Box::new(SetEnvVar("B".to_string(), "2".to_string()))
+        )
+    );
+
+    assert!(action.parse_text("A=1 cat | B=2 wc", "label").is_ok());
+    assert_eq!(
+        action,
+        Pipeline(vec![
+            RunProgram(
+                vec![("A".to_string(), "1".to_string())],
+                "cat".to_string(),
+                vec![],
+                None,
+                None,
+                None
+            ),
+            RunProgram(
+                vec![("B".to_string(), "2".to_string())],
+                "wc".to_string(),
+                vec![],
+                None,
+                None,
+                None
+            ),
+        ])
+    );
+
+    assert!(action.parse_text("A=1 cd x", "label").is_err());
+}
+
+#[test]
+fn test_dir_parse() {
+    use CommandAction::*;
+    let mut action = CommandAction::default();
+    assert!(action.parse_text("cd", "label").is_ok());
+    assert_eq!(action, ChangeDir(None));
+
+    assert!(action.parse_text("cd -", "label").is_ok());
+    assert_eq!(action, ChangeDir(Some("-".to_string())));
+
+    assert!(action
+        .parse_text("cd ~ && pushd sub; popd", "label")
+        .is_ok());
+    assert_eq!(
+        action,
+        Sequence(
+            Box::new(AndList(
+                Box::new(ChangeDir(Some("~".to_string()))),
+                Box::new(PushDir("sub".to_string()))
+            )),
+            Box::new(PopDir)
+        )
+    );
+
+    assert!(action.parse_text("pushd", "label").is_err());
+    assert!(action.parse_text("popd sub", "label").is_err());
+
+    assert!(action.parse_text("echo pushd popd", "label").is_ok());
+    assert_eq!(
+        action,
+        RunProgram(
+            vec![],
+            "echo".to_string(),
+            vec!["pushd".to_string(), "popd".to_string()],
+            None,
+            None,
+            None
+        )
+    );
+}
+
+#[test]
+fn test_fixture_parse() {
+    use CommandAction::*;
+    let mut action = CommandAction::default();
+    assert!(action
+        .parse_text("fixture data/in.txt <<END", "label")
+        .is_ok());
+    assert_eq!(
+        action,
+        CreateFile(
+            "data/in.txt".to_string(),
+            None,
+            "END".to_string(),
+            String::new()
+        )
+    );
+
+    assert!(action
+        .parse_text("fixture run.sh 0755 <<'END'", "label")
+        .is_ok());
+    assert_eq!(
+        action,
+        CreateFile(
+            "run.sh".to_string(),
+            Some("0755".to_string()),
+            "'END'".to_string(),
+            String::new()
+        )
+    );
+
+    assert!(action.parse_text("fixture in.txt", "label").is_err());
+    assert!(action
+        .parse_text("fixture in.txt <<END | cat", "label")
+        .is_err());
+
+    assert!(action.parse_text("cat fixture", "label").is_ok());
+    assert_eq!(
+        action,
+        RunProgram(
+            vec![],
+            "cat".to_string(),
+            vec!["fixture".to_string()],
+            None,
+            None,
+            None
+        )
+    );
+}
+
+#[test]
+fn test_background_parse() {
+    use CommandAction::*;
+    let mut action = CommandAction::default();
+    assert!(action
+        .parse_text("PORT=0 myserver -v | tee log &", "label")
+        .is_ok());
+    assert_eq!(
+        action,
+        Background(vec![
+            RunProgram(
+                vec![("PORT".to_string(), "0".to_string())],
+                "myserver".to_string(),
+                vec!["-v".to_string()],
+                None,
+                None,
+                None
+            ),
+            RunProgram(
+                vec![],
+                "tee".to_string(),
+                vec!["log".to_string()],
+                None,
+                None,
+                None
+            ),
+        ])
+    );
+
+    assert!(action.parse_text("wait", "label").is_ok());
+    assert_eq!(action, Wait(None));
+
+    assert!(action.parse_text("kill $! && wait $!", "label").is_ok());
+    assert_eq!(
+        action,
+        AndList(
+            Box::new(Kill(None, "$!".to_string())),
+            Box::new(Wait(Some("$!".to_string())))
+        )
+    );
+
+    assert!(action.parse_text("kill -TERM 1234", "label").is_ok());
+    assert_eq!(action, Kill(Some("-TERM".to_string()), "1234".to_string()));
+
+    assert!(action.parse_text("a & b", "label").is_err());
+    assert!(action.parse_text("a && b &", "label").is_err());
+    assert!(action.parse_text("kill", "label").is_err());
+
+    assert!(action.parse_text("docker kill x", "label").is_ok());
+    assert_eq!(
+        action,
+        RunProgram(
+            vec![],
+            "docker".to_string(),
+            vec!["kill".to_string(), "x".to_string()],
+            None,
+            None,
+            None
+        )
+    );
+
+    assert!(action
+        .parse_text("kubectl wait pod && mytool wait", "label")
+        .is_ok());
+    assert_eq!(
+        action,
+        AndList(
+            Box::new(RunProgram(
+                vec![],
+                "kubectl".to_string(),
+                vec!["wait".to_string(), "pod".to_string()],
+                None,
+                None,
+                None
+            )),
+            Box::new(RunProgram(
+                vec![],
+                "mytool".to_string(),
+                vec!["wait".to_string()],
+                None,
+                None,
+                None
+            ))
+        )
+    );
+
+    assert!(action
+        .parse_text("echo wait kill fixture pushd popd", "label")
+        .is_ok());
+    assert_eq!(
+        action,
+        RunProgram(
+            vec![],
+            "echo".to_string(),
+            ["wait", "kill", "fixture", "pushd", "popd"]
+                .map(String::from)
+                .to_vec(),
+            None,
+            None,
+            None
         )
     );
 }
@@ -1,12 +1,17 @@
 // Copyright 2024 Peter Williams <pwil3058@gmail.com> <pwil3058@bigpond.net.au>
 
 use std::num::ParseIntError;
+use std::path::PathBuf;
 use std::{error, fmt};
 
 #[derive(Debug)]
 pub enum Error {
+    GoldenFile(PathBuf, std::io::Error),
     IO(std::io::Error),
     MalformedErrorCode(ParseIntError),
+    MalformedRegex(regex::Error),
+    // The program that couldn't be run
+    Spawn(String, std::io::Error),
     Why(&'static str),
 }
 
@@ -22,6 +27,12 @@ impl From<ParseIntError> for Error {
     }
 }
 
+impl From<regex::Error> for Error {
+    fn from(error: regex::Error) -> Self {
+        Error::MalformedRegex(error)
+    }
+}
+
 impl From<&'static str> for Error {
     fn from(str: &'static str) -> Self {
         Self::Why(str)
@@ -31,8 +42,13 @@ impl From<&'static str> for Error {
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
+            Self::GoldenFile(path, err) => {
+                write!(f, "Golden File \"{}\": {err}", path.display())
+            }
             Self::IO(err) => write!(f, "IOError: {err}"),
             Self::MalformedErrorCode(err) => write!(f, "Malformed Error Code: {err}"),
+            Self::MalformedRegex(err) => write!(f, "Malformed Regular Expression: {err}"),
+            Self::Spawn(program, err) => write!(f, "{program}: {err}"),
             Self::Why(reason) => write!(f, "Error: {reason}"),
         }
     }
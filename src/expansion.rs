@@ -0,0 +1,525 @@
+// Copyright 2026 Peter Williams <pwil3058@gmail.com> <pwil3058@bigpond.net.au>
+
+use std::env;
+use std::ffi::{CStr, CString};
+
+use crate::command::Command;
+use crate::error::Error;
+use crate::script::EnvVars;
+
+fn is_name_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+// The length of the variable name (or special parameter "?" or "!") at the start of `text`
+fn name_len(text: &str) -> usize {
+    match text.chars().next() {
+        Some(c) if is_name_start(c) => text.find(|c| !is_name_char(c)).unwrap_or(text.len()),
+        Some('?' | '!') => 1,
+        _ => 0,
+    }
+}
+
+// The home directory of `user` according to the system's user database
+// (which may include sources such as LDAP as well as the password file)
+fn user_home(user: &str) -> Option<String> {
+    let name = CString::new(user).ok()?;
+    let mut buffer: Vec<libc::c_char> = vec![0; 1024];
+    loop {
+        // SAFETY: passwd is plain data for which all zeroes is a valid value
+        let mut passwd: libc::passwd = unsafe { std::mem::zeroed() };
+        let mut result = std::ptr::null_mut();
+        // SAFETY: the pointers are valid for the duration of the call and
+        // buffer.len() is the length of the buffer
+        let status = unsafe {
+            libc::getpwnam_r(
+                name.as_ptr(),
+                &mut passwd,
+                buffer.as_mut_ptr(),
+                buffer.len(),
+                &mut result,
+            )
+        };
+        if status == libc::ERANGE && buffer.len() < 1 << 20 {
+            buffer.resize(buffer.len() * 2, 0);
+        } else if status != 0 || result.is_null() || passwd.pw_dir.is_null() {
+            return None;
+        } else {
+            // SAFETY: pw_dir points to a nul terminated string in buffer
+            let home = unsafe { CStr::from_ptr(passwd.pw_dir) };
+            return Some(home.to_string_lossy().to_string());
+        }
+    }
+}
+
+// The value of the tilde prefix (if any) at the start of `word` (i.e. "~" is
+// the script's home directory and "~user" is the home directory of `user`)
+// and the remainder of `word`.
+fn expand_tilde<'a>(word: &'a str, env_vars: &EnvVars) -> Option<(String, &'a str)> {
+    let prefix = word.strip_prefix('~')?;
+    let (user, rest) = prefix.split_at(prefix.find('/').unwrap_or(prefix.len()));
+    if user.is_empty() {
+        Some((env_vars.var("HOME")?.clone(), rest))
+    } else if user
+        .chars()
+        .all(|c| is_name_char(c) || matches!(c, '.' | '-'))
+    {
+        Some((user_home(user)?, rest))
+    } else {
+        None
+    }
+}
+
+#[derive(Debug, Default)]
+struct Field {
+    text: String,
+    pattern: String,
+    is_pattern: bool,
+}
+
+impl Field {
+    // Expand the field into the (sorted) paths that its pattern matches or,
+    // if it is not a pattern or has no matches, its text.
+    fn glob(self) -> Vec<String> {
+        if self.is_pattern {
+            let options = glob::MatchOptions {
+                require_literal_leading_dot: true,
+                ..glob::MatchOptions::new()
+            };
+            if let Ok(paths) = glob::glob_with(&self.pattern, options) {
+                let mut matches: Vec<String> = paths
+                    .filter_map(Result::ok)
+                    .map(|path| path.to_string_lossy().to_string())
+                    .collect();
+                if !matches.is_empty() {
+                    matches.sort();
+                    return matches;
+                }
+            }
+        }
+        vec![self.text]
+    }
+}
+
+// The fields resulting from the expansion of a word. Only the results of
+// unquoted expansions are split (on white space) into separate fields and
+// only unquoted text is treated as a pattern for pathname expansion.
+struct Fields {
+    split: bool,
+    fields: Vec<Field>,
+    current: Field,
+    in_field: bool,
+}
+
+impl Fields {
+    fn new(split: bool) -> Self {
+        Self {
+            split,
+            fields: vec![],
+            current: Field::default(),
+            in_field: false,
+        }
+    }
+
+    fn push_char(&mut self, c: char) {
+        self.current.text.push(c);
+        self.current
+            .pattern
+            .push_str(&glob::Pattern::escape(c.encode_utf8(&mut [0; 4])));
+        self.in_field = true;
+    }
+
+    fn push_str(&mut self, text: &str) {
+        self.current.text.push_str(text);
+        self.current.pattern.push_str(&glob::Pattern::escape(text));
+        self.in_field = true;
+    }
+
+    fn push_unquoted_char(&mut self, c: char) {
+        self.current.text.push(c);
+        self.current.pattern.push(c);
+        self.current.is_pattern |= matches!(c, '*' | '?' | '[');
+        self.in_field = true;
+    }
+
+    fn push_expansion(&mut self, text: &str) {
+        for c in text.chars() {
+            if self.split && matches!(c, ' ' | '\t' | '\n') {
+                if self.in_field {
+                    self.fields.push(std::mem::take(&mut self.current));
+                    self.in_field = false;
+                }
+            } else {
+                self.push_unquoted_char(c);
+            }
+        }
+    }
+
+    fn finish(mut self) -> Vec<String> {
+        if self.in_field {
+            self.fields.push(self.current);
+        }
+        if self.split {
+            self.fields.into_iter().flat_map(Field::glob).collect()
+        } else {
+            self.fields.into_iter().map(|field| field.text).collect()
+        }
+    }
+}
+
+// Expand the contents of a "${...}" parameter expansion
+fn expand_parameter(parameter: &str, env_vars: &EnvVars) -> Result<String, Error> {
+    let len = name_len(parameter);
+    if len == 0 {
+        return Err(Error::Why("Bad parameter expansion"));
+    }
+    let value = env_vars.var(&parameter[..len]);
+    match &parameter[len..] {
+        "" => Ok(value.cloned().unwrap_or_default()),
+        modifier => match modifier.strip_prefix(":-") {
+            Some(default) => match value {
+                Some(value) if !value.is_empty() => Ok(value.clone()),
+                _ => expand(default, env_vars),
+            },
+            None => Err(Error::Why("Unsupported parameter expansion")),
+        },
+    }
+}
+
+// Run a "$(...)" command substitution (as if in a subshell) and return its
+// standard output without trailing newlines.
+fn substitute_command(cmd_line: &str, env_vars: &EnvVars) -> Result<String, Error> {
+    let command = Command::new(cmd_line)?;
+    let cwd = env::current_dir()?;
+    let outcome = command.run(&mut env_vars.clone());
+    env::set_current_dir(cwd)?;
+    Ok(outcome?.std_out.trim_end_matches('\n').to_string())
+}
+
+// The length of the command in a "$(...)" command substitution (skipping
+// nested parentheses and those in quotes)
+fn substitution_len(text: &str) -> Result<usize, Error> {
+    let mut depth = 0;
+    let mut quote = None;
+    let mut escaped = false;
+    for (index, c) in text.char_indices() {
+        match (quote, c) {
+            _ if escaped => escaped = false,
+            (Some(q), _) if c == q => quote = None,
+            (Some('"') | None, '\\') => escaped = true,
+            (Some(_), _) => (),
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '(') => depth += 1,
+            (None, ')') if depth == 0 => return Ok(index),
+            (None, ')') => depth -= 1,
+            _ => (),
+        }
+    }
+    Err(Error::Why("Unterminated command substitution"))
+}
+
+// Expand the expansion (if any) introduced by a "$" at the start of `text`
+// returning its value and the remainder of `text`.
+fn expand_dollar<'a>(
+    text: &'a str,
+    env_vars: &EnvVars,
+) -> Result<(Option<String>, &'a str), Error> {
+    if let Some(braced) = text.strip_prefix('{') {
+        match braced.find('}') {
+            Some(end) => Ok((
+                Some(expand_parameter(&braced[..end], env_vars)?),
+                &braced[end + 1..],
+            )),
+            None => Err(Error::Why("Unterminated parameter expansion")),
+        }
+    } else if let Some(parenthesized) = text.strip_prefix('(') {
+        let len = substitution_len(parenthesized)?;
+        Ok((
+            Some(substitute_command(&parenthesized[..len], env_vars)?),
+            &parenthesized[len + 1..],
+        ))
+    } else {
+        let len = name_len(text);
+        if len == 0 {
+            Ok((None, text))
+        } else {
+            let value = env_vars.var(&text[..len]).cloned().unwrap_or_default();
+            Ok((Some(value), &text[len..]))
+        }
+    }
+}
+
+// Expand the double quoted string at the start of `text` (after the opening
+// quote) returning the remainder of `text` after the closing quote. If there
+// is no `closing_quote` (as for here-document bodies) all of `text` is expanded
+// and double quotes have no special meaning.
+fn expand_quoted<'a>(
+    text: &'a str,
+    env_vars: &EnvVars,
+    fields: &mut Fields,
+    closing_quote: Option<char>,
+) -> Result<&'a str, Error> {
+    fields.push_str("");
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        rest = &rest[c.len_utf8()..];
+        match c {
+            '"' if closing_quote.is_some() => return Ok(rest),
+            '\\' => match rest.chars().next() {
+                Some(escaped)
+                    if matches!(escaped, '$' | '`' | '\\' | '\n')
+                        || Some(escaped) == closing_quote =>
+                {
+                    fields.push_char(escaped);
+                    rest = &rest[escaped.len_utf8()..];
+                }
+                _ => fields.push_char(c),
+            },
+            '$' => {
+                let (value, remainder) = expand_dollar(rest, env_vars)?;
+                match value {
+                    Some(value) => fields.push_str(&value),
+                    None => fields.push_char(c),
+                }
+                rest = remainder;
+            }
+            _ => fields.push_char(c),
+        }
+    }
+    match closing_quote {
+        Some(_) => Err(Error::Why("Unterminated double quoted string")),
+        None => Ok(rest),
+    }
+}
+
+fn expand_word(word: &str, env_vars: &EnvVars, split: bool) -> Result<Vec<String>, Error> {
+    let mut fields = Fields::new(split);
+    let mut rest = word;
+    if let Some((home, remainder)) = expand_tilde(word, env_vars) {
+        fields.push_str(&home);
+        rest = remainder;
+    }
+    while let Some(c) = rest.chars().next() {
+        rest = &rest[c.len_utf8()..];
+        match c {
+            '\\' => match rest.chars().next() {
+                Some(escaped) => {
+                    fields.push_char(escaped);
+                    rest = &rest[escaped.len_utf8()..];
+                }
+                None => fields.push_char(c),
+            },
+            '\'' => match rest.find('\'') {
+                Some(end) => {
+                    fields.push_str(&rest[..end]);
+                    rest = &rest[end + 1..];
+                }
+                None => return Err(Error::Why("Unterminated single quoted string")),
+            },
+            '"' => rest = expand_quoted(rest, env_vars, &mut fields, Some('"'))?,
+            '$' => {
+                let (value, remainder) = expand_dollar(rest, env_vars)?;
+                match value {
+                    Some(value) => fields.push_expansion(&value),
+                    None => fields.push_char(c),
+                }
+                rest = remainder;
+            }
+            _ => fields.push_unquoted_char(c),
+        }
+    }
+    Ok(fields.finish())
+}
+
+/// Expand `word` as a shell would (but without field splitting): a leading `~`
+/// or `~user`, references to the script's environment and special variables
+/// (`$VAR`, `${VAR}`, `${VAR:-default}` and `$?`) and command substitutions
+/// (`$(command)`) are replaced by their values and quotes and escapes are
+/// removed. Unset variables expand to an empty string.
+pub fn expand(word: &str, env_vars: &EnvVars) -> Result<String, Error> {
+    Ok(expand_word(word, env_vars, false)?
+        .pop()
+        .unwrap_or_default())
+}
+
+/// Expand `word` as for `expand()` and then split the results of any unquoted
+/// expansions into separate fields and replace fields containing unquoted
+/// pattern characters (`*`, `?` and `[...]`) with the sorted list of matching
+/// paths (as a shell does for command arguments).
+pub fn expand_fields(word: &str, env_vars: &EnvVars) -> Result<Vec<String>, Error> {
+    expand_word(word, env_vars, true)
+}
+
+/// Expand the variables and command substitutions in the body of a here-document.
+/// Quotes have no special meaning but backslashes escape `$`, `` ` `` and `\\`.
+pub fn expand_here_doc(body: &str, env_vars: &EnvVars) -> Result<String, Error> {
+    let mut fields = Fields::new(false);
+    expand_quoted(body, env_vars, &mut fields, None)?;
+    Ok(fields.finish().pop().unwrap_or_default())
+}
+
+/// Remove the quotes and escapes from `word` without expanding it (as for the
+/// delimiters of here-documents).
+pub fn remove_quotes(word: &str) -> String {
+    let mut unquoted = String::new();
+    let mut quote = None;
+    let mut chars = word.chars();
+    while let Some(c) = chars.next() {
+        match (c, quote) {
+            ('\'', None) | ('"', None) => quote = Some(c),
+            (c, Some(q)) if c == q => quote = None,
+            ('\\', None) | ('\\', Some('"')) => unquoted.extend(chars.next()),
+            _ => unquoted.push(c),
+        }
+    }
+    unquoted
+}
+
+#[cfg(test)]
+mod expansion_tests {
+    use crate::expansion::{expand, expand_fields, expand_here_doc, remove_quotes};
+    use crate::script::EnvVars;
+
+    #[test]
+    fn expand_vars() {
+        let env_vars = &mut EnvVars::new();
+        env_vars.set_var("HOME", "/home/me");
+        env_vars.set_var("EMPTY", "");
+        assert_eq!(expand("plain", env_vars).unwrap(), "plain");
+        assert_eq!(expand("$HOME/cfg", env_vars).unwrap(), "/home/me/cfg");
+        assert_eq!(expand("${HOME}cfg", env_vars).unwrap(), "/home/mecfg");
+        assert_eq!(expand("x$UNSET_VAR.y", env_vars).unwrap(), "x.y");
+        assert_eq!(expand("${UNSET_VAR:-$HOME}", env_vars).unwrap(), "/home/me");
+        assert_eq!(expand("${EMPTY:-dflt}", env_vars).unwrap(), "dflt");
+        assert_eq!(expand("${HOME:-dflt}", env_vars).unwrap(), "/home/me");
+        assert_eq!(expand("$/$", env_vars).unwrap(), "$/$");
+        assert_eq!(expand("~", env_vars).unwrap(), "/home/me");
+        assert_eq!(expand("~/cfg", env_vars).unwrap(), "/home/me/cfg");
+        assert_eq!(expand("'~'/cfg", env_vars).unwrap(), "~/cfg");
+        assert_eq!(expand("a~", env_vars).unwrap(), "a~");
+        assert_eq!(expand("~root/x", env_vars).unwrap(), "/root/x");
+        assert_eq!(
+            expand("~no_such_user/x", env_vars).unwrap(),
+            "~no_such_user/x"
+        );
+        assert_eq!(expand("~$EMPTY", env_vars).unwrap(), "~");
+        assert_eq!(expand("$?:${?}", env_vars).unwrap(), "0:0");
+        env_vars.set_special("?", "2");
+        assert_eq!(expand("[$?]", env_vars).unwrap(), "[2]");
+        assert!(expand("${HOME", env_vars).is_err());
+        assert!(expand("$(echo", env_vars).is_err());
+        assert!(expand("${1X}", env_vars).is_err());
+    }
+
+    #[test]
+    fn expand_commands() {
+        let env_vars = &mut EnvVars::new();
+        env_vars.set_var("NAME", "world");
+        assert_eq!(
+            expand("<$(echo hello $NAME)>", env_vars).unwrap(),
+            "<hello world>"
+        );
+        assert_eq!(expand("$(seq 2)", env_vars).unwrap(), "1\n2");
+        assert_eq!(expand("x$(NAME=other)$NAME", env_vars).unwrap(), "xworld");
+        assert_eq!(expand(r#"$(echo "(x)")"#, env_vars).unwrap(), "(x)");
+        assert_eq!(expand(r#"$(echo ")" \))"#, env_vars).unwrap(), ") )");
+        assert_eq!(expand("$(echo $(echo in))", env_vars).unwrap(), "in");
+    }
+
+    #[test]
+    fn expand_quotes() {
+        let env_vars = &mut EnvVars::new();
+        env_vars.set_var("NAME", "a  b");
+        assert_eq!(expand(r#"'$NAME \"'"#, env_vars).unwrap(), r#"$NAME \""#);
+        assert_eq!(
+            expand(r#""$NAME \$ \" \x""#, env_vars).unwrap(),
+            r#"a  b $ " \x"#
+        );
+        assert_eq!(expand(r#"\ \$NAME\\"#, env_vars).unwrap(), r#" $NAME\"#);
+        assert_eq!(
+            expand(r#"--name="a b"'c'd"#, env_vars).unwrap(),
+            "--name=a bcd"
+        );
+        assert!(expand(r#""abc"#, env_vars).is_err());
+        assert!(expand("'abc", env_vars).is_err());
+    }
+
+    #[test]
+    fn expand_split_fields() {
+        let env_vars = &mut EnvVars::new();
+        env_vars.set_var("NAME", " a  b ");
+        assert_eq!(expand_fields("x$NAME", env_vars).unwrap(), ["x", "a", "b"]);
+        assert_eq!(expand_fields(r#""x$NAME""#, env_vars).unwrap(), ["x a  b "]);
+        assert_eq!(expand_fields("'a b'", env_vars).unwrap(), ["a b"]);
+        assert!(expand_fields("$UNSET", env_vars).unwrap().is_empty());
+        assert_eq!(expand_fields(r#""""#, env_vars).unwrap(), [""]);
+        assert_eq!(expand_fields("''$UNSET", env_vars).unwrap(), [""]);
+    }
+
+    #[test]
+    fn expand_globs() {
+        let env_vars = &mut EnvVars::new();
+        let tempdir = tempdir::TempDir::new("glob_test").unwrap();
+        let dir = tempdir.path().to_string_lossy().to_string();
+        for file in ["b.txt", "a.txt", ".hidden.txt", "a.rs", "sub/c.txt"] {
+            let path = tempdir.path().join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        }
+        env_vars.set_var("DIR", &dir);
+        assert_eq!(
+            expand_fields("$DIR/*.txt", env_vars).unwrap(),
+            [format!("{dir}/a.txt"), format!("{dir}/b.txt")]
+        );
+        assert_eq!(
+            expand_fields("$DIR/?.*", env_vars).unwrap(),
+            [
+                format!("{dir}/a.rs"),
+                format!("{dir}/a.txt"),
+                format!("{dir}/b.txt")
+            ]
+        );
+        assert_eq!(
+            expand_fields("$DIR/[ab].t*", env_vars).unwrap(),
+            [format!("{dir}/a.txt"), format!("{dir}/b.txt")]
+        );
+        assert_eq!(
+            expand_fields("$DIR/**/c.*", env_vars).unwrap(),
+            [format!("{dir}/sub/c.txt")]
+        );
+        assert_eq!(
+            expand_fields("$DIR/*.none", env_vars).unwrap(),
+            [format!("{dir}/*.none")]
+        );
+        assert_eq!(
+            expand_fields("\"$DIR\"/'*'.txt", env_vars).unwrap(),
+            [format!("{dir}/*.txt")]
+        );
+        assert_eq!(
+            expand_fields("$DIR/\\*.txt", env_vars).unwrap(),
+            [format!("{dir}/*.txt")]
+        );
+        assert_eq!(
+            expand("$DIR/*.txt", env_vars).unwrap(),
+            format!("{dir}/*.txt")
+        );
+    }
+
+    #[test]
+    fn expand_here_docs() {
+        let env_vars = &mut EnvVars::new();
+        env_vars.set_var("NAME", "world");
+        assert_eq!(
+            expand_here_doc("\"hello\" '$NAME'\n\\$NAME \\x\n", env_vars).unwrap(),
+            "\"hello\" 'world'\n$NAME \\x\n"
+        );
+        assert_eq!(remove_quotes("EOF"), "EOF");
+        assert_eq!(remove_quotes("'E\\OF'"), "E\\OF");
+        assert_eq!(remove_quotes("\"E\\\"O\"F"), "E\"OF");
+        assert_eq!(remove_quotes("\\EOF"), "EOF");
+    }
+}
@@ -3,6 +3,7 @@
 mod command;
 mod command_action;
 mod error;
+mod expansion;
 mod script;
 
 use std::io::{self, Write};
@@ -67,7 +68,7 @@ fn main() {
         }
     };
 
-    let result = script.evaluate();
+    let result = script.evaluate(tempdir.as_ref().map(TempDir::path));
 
     if let Some(tempdir) = tempdir
         && let Err(err) = tempdir.close()
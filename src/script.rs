@@ -1,81 +1,691 @@
 // Copyright 2024 Peter Williams <pwil3058@gmail.com> <pwil3058@bigpond.net.au>
 
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::fmt;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::Read;
-use std::ops::Range;
-use std::path::Path;
+use std::ops::{Range, RangeInclusive};
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
-use crate::command::{Command, Outcome};
+use regex::Regex;
+
+use crate::command::{Command, Job, Jobs, Outcome, signal_number};
 use crate::error::Error;
+use crate::expansion::expand_fields;
 
-#[derive(Debug)]
-pub struct EnvVars(pub HashMap<String, String>);
+// The script's environment variables along with the other state (such as
+// the pushd/popd directory stack and background jobs) that is kept between
+// commands. Special variables (such as "?") can be expanded but are not
+// exported to programs and are read-only as far as scripts are concerned.
+#[derive(Debug, Clone)]
+pub struct EnvVars {
+    vars: HashMap<String, String>,
+    specials: HashMap<String, String>,
+    dir_stack: Vec<PathBuf>,
+    jobs: Jobs,
+}
 
 impl EnvVars {
     pub fn new() -> Self {
-        let env_vars: HashMap<String, String> = std::env::vars()
+        let vars: HashMap<String, String> = std::env::vars()
             .filter(|(k, _)| k == "TZ" || k == "LANG" || k == "PATH" || k == "PWD" || k == "HOME")
             .map(|(ref k, ref v)| (k.into(), v.into()))
             .collect();
-        EnvVars(env_vars)
+        let specials = HashMap::from([("?".to_string(), "0".to_string())]);
+        EnvVars {
+            vars,
+            specials,
+            dir_stack: vec![],
+            jobs: Jobs::default(),
+        }
+    }
+
+    pub fn vars(&self) -> &HashMap<String, String> {
+        &self.vars
     }
 
-    #[allow(dead_code)]
     pub fn var(&self, key: &str) -> Option<&String> {
-        self.0.get(key)
+        self.specials.get(key).or_else(|| self.vars.get(key))
+    }
+
+    pub fn is_special(&self, key: &str) -> bool {
+        self.specials.contains_key(key)
+    }
+
+    pub fn set_special(&mut self, key: &str, value: &str) {
+        self.specials.insert(key.to_string(), value.to_string());
     }
 
     pub fn set_var(&mut self, key: &str, value: &str) {
-        self.0.insert(key.to_string(), value.to_string());
+        self.vars.insert(key.to_string(), value.to_string());
     }
 
     pub fn remove_var(&mut self, key: &str) {
-        self.0.remove(key);
+        self.vars.remove(key);
+    }
+
+    pub fn push_dir(&mut self, dir: PathBuf) {
+        self.dir_stack.push(dir);
+    }
+
+    pub fn pop_dir(&mut self) -> Option<PathBuf> {
+        self.dir_stack.pop()
+    }
+
+    pub fn add_job(&mut self, job: Job) {
+        self.jobs.0.push(job);
+    }
+
+    pub fn remove_job(&mut self, pid: u32) -> Option<Job> {
+        let index = self.jobs.0.iter().position(|job| job.pid() == pid)?;
+        Some(self.jobs.0.remove(index))
+    }
+
+    pub fn remove_jobs(&mut self) -> Vec<Job> {
+        std::mem::take(&mut self.jobs.0)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ExpectedLine {
+    Literal(String),
+    // The pattern and its regex which must match the whole of the line (less its newline)
+    Regex(String, Regex),
+    // The text and the regex matching it with each "[..]" matching any text
+    Wildcard(String, Regex),
+    // "..." matches zero or more lines
+    AnyLines,
+    // "@path" the lines of a file (relative to the script) to be read by Script::read_from()
+    Golden(String),
+}
+
+impl ExpectedLine {
+    fn text(text: &str) -> Result<Self, Error> {
+        let line = text.strip_suffix('\n').unwrap_or(text);
+        if line == "..." {
+            Ok(Self::AnyLines)
+        } else if line.contains("[..]") {
+            let parts: Vec<String> = line.split("[..]").map(regex::escape).collect();
+            let regex = Regex::new(&format!("^{}$", parts.join(".*")))?;
+            Ok(Self::Wildcard(text.to_string(), regex))
+        } else {
+            Ok(Self::Literal(text.to_string()))
+        }
+    }
+
+    // Everything after a single separating space is literal (including leading white space)
+    fn exact(text: &str) -> Self {
+        Self::Literal(text.strip_prefix(' ').unwrap_or(text).to_string())
+    }
+
+    // Only a line that is exactly "@path" (with no white space) is a golden
+    // file so that other lines starting with "@" are still text
+    fn text_or_golden(text: &str) -> Result<Self, Error> {
+        let line = text.strip_suffix('\n').unwrap_or(text);
+        match line.strip_prefix('@') {
+            Some(path) if !path.is_empty() && !path.contains(char::is_whitespace) => {
+                Ok(Self::Golden(path.to_string()))
+            }
+            _ => Self::text(text),
+        }
+    }
+
+    fn regex(pattern: &str) -> Result<Self, Error> {
+        let regex = Regex::new(&format!("^(?:{pattern})$"))?;
+        Ok(Self::Regex(pattern.to_string(), regex))
+    }
+
+    fn matches(&self, line: &str) -> bool {
+        match self {
+            Self::Literal(text) => text == line,
+            // as for literal lines, a missing final newline must be expected
+            Self::Regex(_, regex) | Self::Wildcard(_, regex) => line
+                .strip_suffix('\n')
+                .is_some_and(|line| regex.is_match(line)),
+            Self::AnyLines => true,
+            Self::Golden(_) => false,
+        }
+    }
+}
+
+impl fmt::Display for ExpectedLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Literal(text) | Self::Wildcard(text, _) => write!(f, "{text}"),
+            Self::Regex(pattern, _) => writeln!(f, "/{pattern}/"),
+            Self::AnyLines => writeln!(f, "..."),
+            Self::Golden(path) => writeln!(f, "@{path}"),
+        }
+    }
+}
+
+const NO_NEWLINE: &str = "\\ No newline at end of file";
+
+#[derive(Debug, Default, Clone)]
+pub struct ExpectedOutput {
+    lines: Vec<ExpectedLine>,
+    // Compare as a multiset of lines (i.e. ignore their order)
+    unordered: bool,
+    // Don't check the output at all
+    ignored: bool,
+    // The output's last line has no newline
+    no_final_newline: bool,
+    // Convert "\r\n" line endings to "\n" before comparison
+    crlf_to_lf: bool,
+}
+
+fn lines_match(expected: &[ExpectedLine], lines: &[&str]) -> bool {
+    match expected.split_first() {
+        None => lines.is_empty(),
+        Some((ExpectedLine::AnyLines, rest)) => {
+            (0..=lines.len()).any(|skipped| lines_match(rest, &lines[skipped..]))
+        }
+        Some((expected_line, rest)) => match lines.split_first() {
+            Some((line, remaining_lines)) => {
+                expected_line.matches(line) && lines_match(rest, remaining_lines)
+            }
+            None => false,
+        },
+    }
+}
+
+// Try to match `expected[index]` with one of the `lines` (rematching the
+// expected line that already has that line if necessary) where `matched_by`
+// holds the index of the expected line matched with each line (if any).
+fn augment_matching(
+    index: usize,
+    expected: &[&ExpectedLine],
+    lines: &[&str],
+    matched_by: &mut [Option<usize>],
+    visited: &mut [bool],
+) -> bool {
+    for (line_index, line) in lines.iter().enumerate() {
+        if !visited[line_index] && expected[index].matches(line) {
+            visited[line_index] = true;
+            if matched_by[line_index]
+                .is_none_or(|other| augment_matching(other, expected, lines, matched_by, visited))
+            {
+                matched_by[line_index] = Some(index);
+                return true;
+            }
+        }
+    }
+    false
+}
+
+impl ExpectedOutput {
+    fn push(&mut self, line: ExpectedLine) {
+        self.lines.push(line);
+    }
+
+    // Replace any golden file lines with the (literal) lines in the file
+    fn read_golden_files(&mut self, dir: &Path) -> Result<(), Error> {
+        let mut lines = vec![];
+        for line in self.lines.drain(..) {
+            match line {
+                ExpectedLine::Golden(path) => {
+                    let path = dir.join(path);
+                    let text =
+                        fs::read_to_string(&path).map_err(|err| Error::GoldenFile(path, err))?;
+                    lines.extend(
+                        text.split_inclusive('\n')
+                            .map(|line| ExpectedLine::Literal(line.to_string())),
+                    );
+                }
+                line => lines.push(line),
+            }
+        }
+        self.lines = lines;
+        Ok(())
+    }
+
+    // The output as it is to be compared with the expected lines (whose last
+    // line always has a newline) or None if its final newline is wrong
+    fn normalised<'a>(&self, output: &'a str) -> Option<Cow<'a, str>> {
+        let output = if self.crlf_to_lf {
+            Cow::Owned(output.replace("\r\n", "\n"))
+        } else {
+            Cow::Borrowed(output)
+        };
+        if !self.no_final_newline {
+            Some(output)
+        } else if output.is_empty() || output.ends_with('\n') {
+            None
+        } else {
+            Some(Cow::Owned(format!("{output}\n")))
+        }
+    }
+
+    fn matches(&self, output: &str) -> bool {
+        if self.ignored {
+            return true;
+        }
+        let Some(output) = self.normalised(output) else {
+            return false;
+        };
+        if self.unordered {
+            let (missing, unexpected) = self.unordered_differences(&output);
+            missing.is_empty() && unexpected.is_empty()
+        } else {
+            let lines: Vec<&str> = output.split_inclusive('\n').collect();
+            lines_match(&self.lines, &lines)
+        }
+    }
+
+    // The expected lines that have no (distinct) matching line in `output`
+    // and the lines in `output` that aren't matched by an expected line for
+    // a maximum matching of expected lines to lines (so that the result
+    // doesn't depend on the order of the lines).
+    fn unordered_differences<'a>(
+        &'a self,
+        output: &'a str,
+    ) -> (Vec<&'a ExpectedLine>, Vec<&'a str>) {
+        let lines: Vec<&str> = output.split_inclusive('\n').collect();
+        // literal lines first so that they are reported as missing in preference to patterns
+        let (literals, patterns): (Vec<&ExpectedLine>, Vec<&ExpectedLine>) = self
+            .lines
+            .iter()
+            .filter(|line| !matches!(line, ExpectedLine::AnyLines))
+            .partition(|line| matches!(line, ExpectedLine::Literal(_)));
+        let expected: Vec<&ExpectedLine> = literals.into_iter().chain(patterns).collect();
+        let mut matched_by = vec![None; lines.len()];
+        let mut missing = vec![];
+        for (index, expected_line) in expected.iter().enumerate() {
+            let mut visited = vec![false; lines.len()];
+            if !augment_matching(index, &expected, &lines, &mut matched_by, &mut visited) {
+                missing.push(*expected_line);
+            }
+        }
+        let mut unmatched: Vec<&str> = lines
+            .iter()
+            .zip(matched_by)
+            .filter_map(|(line, matched_by)| matched_by.is_none().then_some(*line))
+            .collect();
+        if self
+            .lines
+            .iter()
+            .any(|line| matches!(line, ExpectedLine::AnyLines))
+        {
+            unmatched.clear();
+        }
+        (missing, unmatched)
+    }
+
+    fn write_mismatch(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        stream: &str,
+        output: &str,
+    ) -> fmt::Result {
+        match self.normalised(output) {
+            Some(output) if self.unordered => {
+                let (missing, unexpected) = self.unordered_differences(&output);
+                write!(f, "\tMissing {stream} Lines: ")?;
+                for line in missing {
+                    write!(f, "{line}")?;
+                }
+                write!(f, "\tUnexpected {stream} Lines: {}", unexpected.concat())
+            }
+            _ => {
+                write!(
+                    f,
+                    "\tExpected {stream}: {self}\t  Actual {stream}: {output}"
+                )?;
+                if output.is_empty() || output.ends_with('\n') {
+                    Ok(())
+                } else {
+                    writeln!(f, "\n{NO_NEWLINE}")
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for ExpectedOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in self.lines.iter() {
+            write!(f, "{line}")?;
+        }
+        if self.no_final_newline {
+            writeln!(f, "{NO_NEWLINE}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExpectedStatus {
+    // An exit code in one of the (inclusive) ranges e.g. "0", "1,2" or "64-78"
+    Codes(Vec<RangeInclusive<i32>>),
+    // "!0" any exit code other than zero or killed by a signal
+    Failure,
+    // "" killed by any signal
+    Killed,
+    // "SIGTERM" or "signal 9" killed by the signal
+    Signal(i32),
+}
+
+impl ExpectedStatus {
+    fn matches(&self, outcome: &Outcome) -> bool {
+        match self {
+            Self::Codes(ranges) => outcome
+                .e_code
+                .is_some_and(|e_code| ranges.iter().any(|range| range.contains(&e_code))),
+            Self::Failure => outcome.e_code != Some(0),
+            Self::Killed => outcome.e_code.is_none(),
+            Self::Signal(signal) => outcome.signal == Some(*signal),
+        }
+    }
+}
+
+impl FromStr for ExpectedStatus {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        if text.is_empty() {
+            Ok(Self::Killed)
+        } else if text == "!0" {
+            Ok(Self::Failure)
+        } else if let Some(signal) = text.strip_prefix("signal ") {
+            Ok(Self::Signal(signal_number(signal.trim())?))
+        } else if text.starts_with("SIG") {
+            Ok(Self::Signal(signal_number(text)?))
+        } else {
+            let mut ranges = vec![];
+            for item in text.split(',').map(str::trim) {
+                match item.split_once('-') {
+                    Some((low, high)) => ranges.push(i32::from_str(low)?..=i32::from_str(high)?),
+                    None => {
+                        let e_code = i32::from_str(item)?;
+                        ranges.push(e_code..=e_code);
+                    }
+                }
+            }
+            Ok(Self::Codes(ranges))
+        }
+    }
+}
+
+impl fmt::Display for ExpectedStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Codes(ranges) => {
+                let items: Vec<String> = ranges
+                    .iter()
+                    .map(|range| {
+                        if range.start() == range.end() {
+                            range.start().to_string()
+                        } else {
+                            format!("{}-{}", range.start(), range.end())
+                        }
+                    })
+                    .collect();
+                write!(f, "{}", items.join(","))
+            }
+            Self::Failure => write!(f, "!0"),
+            Self::Killed => write!(f, "\"killed\""),
+            Self::Signal(signal) => write!(f, "signal {signal}"),
+        }
+    }
+}
+
+// Assertions about the files left by a command. Paths are expanded (as
+// command arguments are) when the assertion is checked.
+#[derive(Debug, Clone)]
+pub enum FileAssertion {
+    Exists(String),
+    Absent(String),
+    IsDir(String),
+    // The permission bits e.g. 755
+    Mode(String, u32),
+    Contents(String, String),
+    // The golden file (relative to the script) to be read by Script::read_from()
+    GoldenContents(String, String),
+}
+
+impl FileAssertion {
+    fn path(&self) -> &str {
+        match self {
+            Self::Exists(path)
+            | Self::Absent(path)
+            | Self::IsDir(path)
+            | Self::Mode(path, _)
+            | Self::Contents(path, _)
+            | Self::GoldenContents(path, _) => path,
+        }
+    }
+
+    // Replace golden file contents with the contents of the file
+    fn read_golden_file(&mut self, dir: &Path) -> Result<(), Error> {
+        if let Self::GoldenContents(path, golden) = self {
+            let golden = dir.join(golden.as_str());
+            let contents =
+                fs::read_to_string(&golden).map_err(|err| Error::GoldenFile(golden, err))?;
+            *self = Self::Contents(path.clone(), contents);
+        }
+        Ok(())
+    }
+
+    // How the file at `path` differs from that expected (if it does)
+    fn difference(&self, path: &Path) -> Option<String> {
+        let Ok(metadata) = fs::metadata(path) else {
+            return match self {
+                Self::Absent(_) => None,
+                _ => Some("does not exist".to_string()),
+            };
+        };
+        match self {
+            Self::Exists(_) => None,
+            Self::Absent(_) => Some("exists".to_string()),
+            Self::IsDir(_) => (!metadata.is_dir()).then(|| "is not a directory".to_string()),
+            Self::Mode(_, mode) => {
+                let actual = metadata.permissions().mode() & 0o7777;
+                (actual != *mode).then(|| format!("has mode {actual:o} not {mode:o}"))
+            }
+            Self::Contents(_, contents) => match fs::read_to_string(path) {
+                Ok(actual) if actual == *contents => None,
+                Ok(actual) => Some(format!(
+                    "Expected Contents: {contents}\t  Actual Contents: {actual}"
+                )),
+                Err(err) => Some(format!("can't be read: {err}")),
+            },
+            Self::GoldenContents(_, golden) => {
+                Some(format!("golden file \"{golden}\" has not been read"))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ExpectedOutcome {
+    pub status: ExpectedStatus,
+    pub std_out: ExpectedOutput,
+    pub std_err: ExpectedOutput,
+    pub files: Vec<FileAssertion>,
+}
+
+impl Default for ExpectedOutcome {
+    fn default() -> Self {
+        Self {
+            status: ExpectedStatus::Codes(vec![0..=0]),
+            std_out: ExpectedOutput::default(),
+            std_err: ExpectedOutput::default(),
+            files: vec![],
+        }
+    }
+}
+
+impl ExpectedOutcome {
+    // The expected outputs named in a directive (or both if none are named)
+    fn outputs_mut(&mut self, names: &[&str]) -> Result<Vec<&mut ExpectedOutput>, Error> {
+        let mut std_out = names.is_empty();
+        let mut std_err = names.is_empty();
+        for name in names {
+            match *name {
+                "stdout" => std_out = true,
+                "stderr" => std_err = true,
+                _ => return Err(Error::Why("Unknown stream in directive")),
+            }
+        }
+        let mut outputs = vec![];
+        if std_out {
+            outputs.push(&mut self.std_out);
+        }
+        if std_err {
+            outputs.push(&mut self.std_err);
+        }
+        Ok(outputs)
+    }
+
+    // Apply a "%" directive line modifying how the outcome is compared or
+    // adding a file assertion and return the number of the `following_lines`
+    // (i.e. a here-document) that it uses
+    fn apply_directive(
+        &mut self,
+        directive: &str,
+        following_lines: &[&str],
+    ) -> Result<usize, Error> {
+        let words = split_words(directive)?;
+        match words.split_first() {
+            Some((&"exists", [path])) => {
+                self.files.push(FileAssertion::Exists(path.to_string()));
+                Ok(0)
+            }
+            Some((&"absent", [path])) => {
+                self.files.push(FileAssertion::Absent(path.to_string()));
+                Ok(0)
+            }
+            Some((&"dir", [path])) => {
+                self.files.push(FileAssertion::IsDir(path.to_string()));
+                Ok(0)
+            }
+            Some((&"mode", [path, mode])) => {
+                let mode =
+                    u32::from_str_radix(mode, 8).map_err(|_| Error::Why("Malformed file mode"))?;
+                self.files.push(FileAssertion::Mode(path.to_string(), mode));
+                Ok(0)
+            }
+            Some((&"contents", [path, source])) => {
+                if let Some(golden) = source.strip_prefix('@') {
+                    let assertion =
+                        FileAssertion::GoldenContents(path.to_string(), golden.to_string());
+                    self.files.push(assertion);
+                    Ok(0)
+                } else if let Some(delimiter) = source.strip_prefix("<<") {
+                    let (contents, n_lines) = read_here_doc(following_lines, delimiter)?;
+                    self.files
+                        .push(FileAssertion::Contents(path.to_string(), contents));
+                    Ok(n_lines)
+                } else {
+                    Err(Error::Why("Malformed contents directive"))
+                }
+            }
+            Some((&"unordered", names)) => {
+                for output in self.outputs_mut(names)? {
+                    output.unordered = true;
+                }
+                Ok(0)
+            }
+            Some((&"ignore", names)) => {
+                for output in self.outputs_mut(names)? {
+                    output.ignored = true;
+                }
+                Ok(0)
+            }
+            Some((&"crlf-to-lf", names)) => {
+                for output in self.outputs_mut(names)? {
+                    output.crlf_to_lf = true;
+                }
+                Ok(0)
+            }
+            _ => Err(Error::Why("Unknown directive")),
+        }
+    }
+
+    // Golden files are only read by Script::read_from()
+    fn has_unread_golden_files(&self) -> bool {
+        let is_golden = |line: &ExpectedLine| matches!(line, ExpectedLine::Golden(_));
+        self.std_out.lines.iter().any(is_golden)
+            || self.std_err.lines.iter().any(is_golden)
+            || self
+                .files
+                .iter()
+                .any(|assertion| matches!(assertion, FileAssertion::GoldenContents(_, _)))
+    }
+
+    fn matches(&self, outcome: &Outcome) -> bool {
+        self.status.matches(outcome)
+            && self.std_out.matches(&outcome.std_out)
+            && self.std_err.matches(&outcome.std_err)
     }
 }
 
 #[derive(Debug)]
 struct CommandAndExpectedOutcome {
     command: Command,
-    expected_outcome: Outcome,
+    expected_outcome: ExpectedOutcome,
     range: Range<usize>,
 }
 
 impl CommandAndExpectedOutcome {
     pub fn evaluate(&self, env_vars: &mut EnvVars) -> Result<Evaluation, Error> {
         let outcome = self.command.run(env_vars)?;
-        if outcome == self.expected_outcome {
-            Ok(Evaluation::Pass)
-        } else {
-            Ok(Evaluation::Fail(
+        if !self.expected_outcome.matches(&outcome) {
+            return Ok(Evaluation::Fail(
                 self.range.clone(),
                 self.command.cmd_line_string.clone(),
                 self.expected_outcome.clone(),
                 outcome,
-            ))
+            ));
         }
+        for assertion in self.expected_outcome.files.iter() {
+            // as for a command argument, a pattern may match several paths
+            let paths = expand_fields(assertion.path(), env_vars)?;
+            if paths.is_empty() {
+                return Ok(Evaluation::FileFail(
+                    self.range.clone(),
+                    self.command.cmd_line_string.clone(),
+                    assertion.path().to_string(),
+                    "expands to no paths".to_string(),
+                ));
+            }
+            for path in paths {
+                if let Some(difference) = assertion.difference(Path::new(&path)) {
+                    return Ok(Evaluation::FileFail(
+                        self.range.clone(),
+                        self.command.cmd_line_string.clone(),
+                        path,
+                        difference,
+                    ));
+                }
+            }
+        }
+        Ok(Evaluation::Pass)
     }
 }
 
 #[derive(Debug, Default)]
 pub struct Script {
     commands: Vec<CommandAndExpectedOutcome>,
+    test_dir: Option<PathBuf>,
 }
 
 #[derive(Debug)]
 pub enum Evaluation {
     Pass,
-    Fail(Range<usize>, String, Outcome, Outcome),
+    Fail(Range<usize>, String, ExpectedOutcome, Outcome),
+    // The path of the file that failed an assertion and how it differs
+    FileFail(Range<usize>, String, String, String),
 }
 
 impl Evaluation {
     pub fn failed(&self) -> bool {
         match self {
             Self::Pass => false,
-            Self::Fail(_, _, _, _) => true,
+            Self::Fail(_, _, _, _) | Self::FileFail(_, _, _, _) => true,
         }
     }
 }
@@ -84,33 +694,37 @@ impl fmt::Display for Evaluation {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Pass => write!(f, "PASS"),
+            Self::FileFail(range, cl_string, path, difference) => {
+                write!(
+                    f,
+                    "FAIL: {range:?}: {cl_string}\tFile \"{path}\" {difference}"
+                )
+            }
             Self::Fail(range, cl_string, expected, actual) => {
                 write!(f, "FAIL: {range:?}: {cl_string}")?;
-                if expected.e_code != actual.e_code {
-                    if let Some(e_e_code) = expected.e_code {
-                        if let Some(a_e_code) = actual.e_code {
-                            write!(f, "\tExpected Error Code {e_e_code} got {a_e_code}")?;
-                        } else {
-                            write!(f, "\tExpected Error Code {e_e_code} got \"killed\"")?;
+                if !expected.status.matches(actual) {
+                    let e_status = &expected.status;
+                    match (actual.e_code, actual.signal) {
+                        (Some(a_e_code), _) => {
+                            write!(f, "\tExpected Error Code {e_status} got {a_e_code}")?
+                        }
+                        (None, Some(signal)) => {
+                            write!(f, "\tExpected Error Code {e_status} got signal {signal}")?
+                        }
+                        (None, None) => {
+                            write!(f, "\tExpected Error Code {e_status} got \"killed\"")?
                         }
-                    } else {
-                        let a_e_code = actual.e_code.expect("Should NOT be None");
-                        write!(f, "\tExpected Error Code \"killed\" got {a_e_code}")?;
                     }
                 }
-                if expected.std_out != actual.std_out {
-                    write!(
-                        f,
-                        "\tExpected Stdout: {}\t  Actual Stdout: {}",
-                        expected.std_out, actual.std_out
-                    )?;
+                if !expected.std_out.matches(&actual.std_out) {
+                    expected
+                        .std_out
+                        .write_mismatch(f, "Stdout", &actual.std_out)?;
                 }
-                if expected.std_err != actual.std_err {
-                    write!(
-                        f,
-                        "\tExpected Stderr: {}\t  Actual Stderr: {}",
-                        expected.std_err, actual.std_err
-                    )?;
+                if !expected.std_err.matches(&actual.std_err) {
+                    expected
+                        .std_err
+                        .write_mismatch(f, "Stderr", &actual.std_err)?;
                 }
                 Ok(())
             }
@@ -124,6 +738,68 @@ fn read_script<R: Read>(mut reader: R) -> Result<String, Error> {
     Ok(script)
 }
 
+// The text of `line` without its trailing "\" and newline if (as in a shell)
+// it is continued on the next line.
+fn strip_continuation(line: &str) -> Option<&str> {
+    let line = line.strip_suffix('\n')?;
+    let unescaped = line.trim_end_matches('\\');
+    if (line.len() - unescaped.len()) % 2 == 1 {
+        Some(&line[..line.len() - 1])
+    } else {
+        None
+    }
+}
+
+// Split `text` into (unexpanded) words at unquoted and unescaped white space
+// as a shell does.
+fn split_words(text: &str) -> Result<Vec<&str>, Error> {
+    let mut words = vec![];
+    let mut start = None;
+    let mut quote = None;
+    let mut escaped = false;
+    for (index, c) in text.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if let Some(q) = quote {
+            if c == q {
+                quote = None;
+            } else if c == '\\' && q == '"' {
+                escaped = true;
+            }
+        } else if c.is_whitespace() {
+            if let Some(start) = start.take() {
+                words.push(&text[start..index]);
+            }
+            continue;
+        } else if c == '\'' || c == '"' {
+            quote = Some(c);
+        } else if c == '\\' {
+            escaped = true;
+        }
+        start.get_or_insert(index);
+    }
+    if quote.is_some() || escaped {
+        return Err(Error::Why("Unterminated quote or escape"));
+    }
+    if let Some(start) = start {
+        words.push(&text[start..]);
+    }
+    Ok(words)
+}
+
+// The body of the here-document at the start of `lines` and the number of
+// lines (including the delimiter line) that it occupies
+fn read_here_doc(lines: &[&str], delimiter: &str) -> Result<(String, usize), Error> {
+    let mut body = String::new();
+    for (index, line) in lines.iter().enumerate() {
+        if line.trim_end_matches('\n') == delimiter {
+            return Ok((body, index + 1));
+        }
+        body.push_str(line);
+    }
+    Err(Error::Why("Unterminated here-document"))
+}
+
 impl Script {
     pub fn read<R: Read>(reader: R) -> Result<Self, Error> {
         let script = read_script(reader)?;
@@ -132,25 +808,87 @@ impl Script {
         let mut i = 0;
         while let Some(line) = lines.get(i) {
             if let Some(stripped) = line.strip_prefix('$') {
-                let command = Command::new(stripped)?;
-                let mut expected_outcome = Outcome::default();
                 // line numbers start at 1
                 let start = i + 1;
+                let mut cmd_line = stripped.to_string();
+                while let Some(continued) = strip_continuation(&cmd_line) {
+                    let Some(next_line) = lines.get(i + 1) else {
+                        break;
+                    };
+                    cmd_line = format!(
+                        "{continued}{}",
+                        next_line.strip_prefix("$>").unwrap_or(next_line)
+                    );
+                    i += 1;
+                }
+                let mut command = match cmd_line.strip_prefix("sh ") {
+                    Some(shell_cmd_line) => Command::new_shell(shell_cmd_line),
+                    None => Command::new(&cmd_line)?,
+                };
+                let mut expected_outcome = ExpectedOutcome::default();
+                // which output the most recent expected line was for
+                let mut last_is_std_err = None;
                 i += 1;
+                for (delimiter, body) in command.here_docs_mut() {
+                    let (text, n_lines) = read_here_doc(&lines[i..], &delimiter)?;
+                    body.push_str(&text);
+                    i += n_lines;
+                }
                 while let Some(line) = lines.get(i) {
                     if line.starts_with('$') {
                         break;
                     } else if let Some(stripped) = line.strip_prefix('?') {
-                        let trimmed = stripped.trim();
-                        if trimmed.is_empty() {
-                            expected_outcome.e_code = None;
-                        } else {
-                            expected_outcome.e_code = Some(i32::from_str(trimmed)?);
-                        }
+                        expected_outcome.status = ExpectedStatus::from_str(stripped.trim())?;
+                    } else if let Some(exact) = line.strip_prefix("!|") {
+                        let expected_line = ExpectedLine::exact(exact);
+                        expected_outcome.std_err.push(expected_line);
+                        last_is_std_err = Some(true);
+                    } else if let Some(exact) = line.strip_prefix(">|") {
+                        let expected_line = ExpectedLine::exact(exact);
+                        expected_outcome.std_out.push(expected_line);
+                        last_is_std_err = Some(false);
                     } else if let Some(trimmed) = line.strip_prefix('!') {
-                        expected_outcome.std_err.push_str(trimmed.trim_start());
+                        let expected_line = ExpectedLine::text_or_golden(trimmed.trim_start())?;
+                        expected_outcome.std_err.push(expected_line);
+                        last_is_std_err = Some(true);
                     } else if let Some(trimmed) = line.strip_prefix('>') {
-                        expected_outcome.std_out.push_str(trimmed.trim_start());
+                        let expected_line = ExpectedLine::text_or_golden(trimmed.trim_start())?;
+                        expected_outcome.std_out.push(expected_line);
+                        last_is_std_err = Some(false);
+                    } else if let Some(pattern) = line.strip_prefix("~!") {
+                        let expected_line =
+                            ExpectedLine::regex(pattern.trim_start().trim_end_matches('\n'))?;
+                        expected_outcome.std_err.push(expected_line);
+                        last_is_std_err = Some(true);
+                    } else if let Some(pattern) = line.strip_prefix("~>") {
+                        let expected_line =
+                            ExpectedLine::regex(pattern.trim_start().trim_end_matches('\n'))?;
+                        expected_outcome.std_out.push(expected_line);
+                        last_is_std_err = Some(false);
+                    } else if line.trim() == NO_NEWLINE {
+                        // as used by diff (other lines starting with "\" are comments)
+                        match last_is_std_err {
+                            Some(true) => expected_outcome.std_err.no_final_newline = true,
+                            Some(false) => expected_outcome.std_out.no_final_newline = true,
+                            None => {
+                                return Err(Error::Why(
+                                    "No newline marker must follow an expected output line",
+                                ));
+                            }
+                        }
+                    } else if let Some(directive) = line.strip_prefix('%') {
+                        i += expected_outcome.apply_directive(directive, &lines[i + 1..])?;
+                    } else if let Some(exact) = line.strip_prefix("<|") {
+                        // everything after a single separating space (as for ">|")
+                        command
+                            .std_in
+                            .get_or_insert_with(String::new)
+                            .push_str(exact.strip_prefix(' ').unwrap_or(exact));
+                    } else if let Some(trimmed) = line.strip_prefix('<') {
+                        command
+                            .std_in
+                            .get_or_insert_with(String::new)
+                            .push_str(trimmed.trim_start());
                     }
                     i += 1;
                 }
@@ -165,15 +903,45 @@ impl Script {
                 i += 1
             }
         }
-        Ok(Self { commands })
+        Ok(Self {
+            commands,
+            test_dir: None,
+        })
     }
 
     pub fn read_from(path: &Path) -> Result<Self, Error> {
-        Self::read(File::open(path)?)
+        let mut script = Self::read(File::open(path)?)?;
+        // absolute as the script will probably be run in another directory
+        script.test_dir = path.canonicalize()?.parent().map(Path::to_path_buf);
+        if let Some(test_dir) = &script.test_dir {
+            for command in script.commands.iter_mut() {
+                let expected_outcome = &mut command.expected_outcome;
+                expected_outcome.std_out.read_golden_files(test_dir)?;
+                expected_outcome.std_err.read_golden_files(test_dir)?;
+                for assertion in expected_outcome.files.iter_mut() {
+                    assertion.read_golden_file(test_dir)?;
+                }
+            }
+        }
+        Ok(script)
     }
 
-    pub fn evaluate(&self) -> Result<Evaluation, Error> {
+    pub fn evaluate(&self, tmpdir_root: Option<&Path>) -> Result<Evaluation, Error> {
+        if self
+            .commands
+            .iter()
+            .any(|command| command.expected_outcome.has_unread_golden_files())
+        {
+            return Err(Error::Why("Golden files have not been read"));
+        }
         let mut env_vars = EnvVars::new();
+        if let Some(test_dir) = &self.test_dir {
+            env_vars.set_special("TESTDIR", &test_dir.to_string_lossy());
+        }
+        if let Some(tmpdir_root) = tmpdir_root {
+            env_vars.set_special("TMPDIR_ROOT", &tmpdir_root.to_string_lossy());
+        }
+        // any background jobs still running are killed when env_vars is dropped
         for command in self.commands.iter() {
             let evaluation = command.evaluate(&mut env_vars)?;
             if evaluation.failed() {
@@ -183,3 +951,372 @@ impl Script {
         Ok(Evaluation::Pass)
     }
 }
+
+#[cfg(test)]
+mod script_tests {
+    use crate::command::Outcome;
+    use crate::command_action::CommandAction;
+    use crate::error::Error;
+    use crate::script::{Evaluation, ExpectedStatus, Script};
+
+    #[test]
+    fn read_continuation() {
+        let text = "$ printf '%s,' a \\\n$>   b \\\n  c\n> a,b,c,\n$ echo x\\\\\n";
+        let script = Script::read(text.as_bytes()).unwrap();
+        assert_eq!(script.commands.len(), 2);
+        assert_eq!(
+            script.commands[0].command.cmd_line_string,
+            " printf '%s,' a    b   c\n"
+        );
+        assert_eq!(script.commands[0].range, 1..5);
+        assert_eq!(
+            script.commands[0].expected_outcome.std_out.to_string(),
+            "a,b,c,\n"
+        );
+        assert_eq!(script.commands[1].command.cmd_line_string, " echo x\\\\\n");
+        assert_eq!(script.commands[1].range, 5..6);
+        assert!(Script::read("$ echo \\\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn read_shell() {
+        let text = "$sh for i in 1 2; do \\\n$>   echo $i; done\n> 1\n> 2\n$ sh -c 'exit 1'\n? 1\n";
+        let script = Script::read(text.as_bytes()).unwrap();
+        assert_eq!(
+            script.commands[0].command.cmd_action,
+            CommandAction::Shell("for i in 1 2; do    echo $i; done".to_string(), vec![])
+        );
+        assert!(matches!(
+            script.commands[1].command.cmd_action,
+            CommandAction::RunProgram(..)
+        ));
+        assert!(
+            script
+                .evaluate(None)
+                .is_ok_and(|evaluation| !evaluation.failed())
+        );
+
+        let text = "$sh cat <<EOF | tr a-z A-Z\nhello\n> body\nEOF\n> HELLO\n> > BODY\n";
+        let script = Script::read(text.as_bytes()).unwrap();
+        assert_eq!(
+            script.commands[0].command.cmd_action,
+            CommandAction::Shell(
+                "cat <<EOF | tr a-z A-Z".to_string(),
+                vec![("EOF".to_string(), "hello\n> body\n".to_string())]
+            )
+        );
+        let evaluation = script.evaluate(None).unwrap();
+        assert!(!evaluation.failed(), "{evaluation}");
+    }
+
+    #[test]
+    fn read_regex() {
+        let text = "$ echo took 12ms; echo done\n~> took \\d+ms\n> done\n~! \n";
+        let script = Script::read(text.as_bytes()).unwrap();
+        let expected = &script.commands[0].expected_outcome;
+        assert_eq!(expected.std_out.to_string(), "/took \\d+ms/\ndone\n");
+        assert!(expected.std_out.matches("took 345ms\ndone\n"));
+        assert!(!expected.std_out.matches("took 345ms!\ndone\n"));
+        assert!(!expected.std_out.matches("took 345ms\n"));
+        assert!(expected.std_err.matches("\n"));
+        assert!(!expected.std_err.matches(""));
+
+        assert!(Script::read("$ echo\n~> (\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn read_wildcards() {
+        let text = "$ ls -l\n> total [..]\n> ...\n> [..] a.(txt)\n> ...\n! ...\n! fatal: [..]\n";
+        let script = Script::read(text.as_bytes()).unwrap();
+        let expected = &script.commands[0].expected_outcome;
+        assert_eq!(
+            expected.std_out.to_string(),
+            "total [..]\n...\n[..] a.(txt)\n...\n"
+        );
+        assert!(expected.std_out.matches("total 8\n-rw 1 a.(txt)\n"));
+        assert!(expected.std_out.matches("total 8\nx\ny\nz a.(txt)\nw\n"));
+        assert!(!expected.std_out.matches("total 8\n-rw 1 a.txt\n"));
+        assert!(!expected.std_out.matches("8 total\n-rw 1 a.(txt)\n"));
+        assert!(expected.std_err.matches("fatal: \n"));
+        assert!(expected.std_err.matches("warning\nfatal: oops\n"));
+        assert!(!expected.std_err.matches("fatal: oops\nwarning\n"));
+    }
+
+    #[test]
+    fn read_unordered() {
+        let text = "$ ls\n% unordered stdout\n> b\n> a\n> [..].rs\n> a\n! x\n! y\n";
+        let script = Script::read(text.as_bytes()).unwrap();
+        let expected = &script.commands[0].expected_outcome;
+        assert!(expected.std_out.matches("a\nlib.rs\nb\na\n"));
+        assert!(!expected.std_out.matches("a\nlib.rs\nb\n"));
+        assert!(!expected.std_err.matches("y\nx\n"));
+        let (missing, unexpected) = expected.std_out.unordered_differences("a\nc\nb\nc\n");
+        let missing: Vec<String> = missing.iter().map(|line| line.to_string()).collect();
+        assert_eq!(missing, ["a\n", "[..].rs\n"]);
+        assert_eq!(unexpected, ["c\n", "c\n"]);
+
+        // the first pattern mustn't keep the only line that the second matches
+        let text = "$ ls\n% unordered stdout\n> [..]\n> b[..]\n";
+        let script = Script::read(text.as_bytes()).unwrap();
+        let expected = &script.commands[0].expected_outcome;
+        assert!(expected.std_out.matches("b1\nx\n"));
+        assert!(expected.std_out.matches("x\nb1\n"));
+        assert!(!expected.std_out.matches("x\ny\n"));
+
+        let text = "$ ls\n% unordered\n! x\n! y\n";
+        let script = Script::read(text.as_bytes()).unwrap();
+        let expected = &script.commands[0].expected_outcome;
+        assert!(expected.std_out.unordered && expected.std_err.unordered);
+
+        assert!(Script::read("$ ls\n% unordered stdin\n".as_bytes()).is_err());
+        assert!(Script::read("$ ls\n% sorted\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn read_ignore() {
+        let text = "$ sh -c 'echo 10%; echo out; exit 1'\n% ignore stderr\n? 1\n> out\n";
+        let script = Script::read(text.as_bytes()).unwrap();
+        let expected = &script.commands[0].expected_outcome;
+        assert!(expected.std_err.matches("progress: 10%\nprogress: 90%\n"));
+        assert!(expected.std_err.matches(""));
+        assert!(!expected.std_out.matches(""));
+        let outcome = Outcome {
+            e_code: Some(1),
+            signal: None,
+            std_out: "out\n".to_string(),
+            std_err: "noise\n".to_string(),
+        };
+        assert!(expected.matches(&outcome));
+        assert!(!expected.matches(&Outcome {
+            e_code: Some(0),
+            ..outcome
+        }));
+
+        let text = "$ ls\n% ignore\n";
+        let script = Script::read(text.as_bytes()).unwrap();
+        let expected = &script.commands[0].expected_outcome;
+        assert!(expected.std_out.matches("x\n") && expected.std_err.matches("y\n"));
+    }
+
+    #[test]
+    fn read_golden() {
+        let tmp_dir = tempdir::TempDir::new("golden").unwrap();
+        let script_path = tmp_dir.path().join("test.script");
+        std::fs::create_dir(tmp_dir.path().join("golden")).unwrap();
+        std::fs::write(tmp_dir.path().join("golden/out.txt"), "one\ntwo\n").unwrap();
+        std::fs::write(
+            &script_path,
+            "$ ls\n> @golden/out.txt\n> three\n! @missing.txt\n",
+        )
+        .unwrap();
+        match Script::read_from(&script_path) {
+            Err(Error::GoldenFile(path, _)) => assert!(path.ends_with("missing.txt")),
+            result => panic!("unexpected result: {result:?}"),
+        }
+        std::fs::write(&script_path, "$ ls\n> @golden/out.txt\n> three\n").unwrap();
+        let script = Script::read_from(&script_path).unwrap();
+        let expected = &script.commands[0].expected_outcome;
+        assert!(expected.std_out.matches("one\ntwo\nthree\n"));
+        assert!(!expected.std_out.matches("one\nthree\n"));
+
+        let text = "$ ls\n> @scope/pkg 1.0\n>| @user\n> @ end\n";
+        let script = Script::read(text.as_bytes()).unwrap();
+        let expected = &script.commands[0].expected_outcome;
+        assert!(expected.std_out.matches("@scope/pkg 1.0\n@user\n@ end\n"));
+
+        let script = Script::read("$ ls\n> @golden/out.txt\n".as_bytes()).unwrap();
+        assert!(script.evaluate(None).is_err());
+    }
+
+    #[test]
+    fn read_exact() {
+        let text = "$ ls\n>|   indented\n>|\tname: [..]\n>|\n!| \t...\n";
+        let script = Script::read(text.as_bytes()).unwrap();
+        let expected = &script.commands[0].expected_outcome;
+        assert!(expected.std_out.matches("  indented\n\tname: [..]\n\n"));
+        assert!(!expected.std_out.matches("indented\n\tname: [..]\n\n"));
+        assert!(!expected.std_out.matches("  indented\n\tname: x\n\n"));
+        assert!(expected.std_err.matches("\t...\n"));
+        assert!(!expected.std_err.matches(""));
+    }
+
+    #[test]
+    fn read_no_newline() {
+        let text = "$ printf 'a\\nb'\n> a\n> b\n\\ No newline at end of file\n! c\n";
+        let script = Script::read(text.as_bytes()).unwrap();
+        let expected = &script.commands[0].expected_outcome;
+        assert!(expected.std_out.matches("a\nb"));
+        assert!(!expected.std_out.matches("a\nb\n"));
+        assert!(expected.std_err.matches("c\n"));
+        assert!(!expected.std_err.matches("c"));
+        assert_eq!(
+            expected.std_out.to_string(),
+            "a\nb\n\\ No newline at end of file\n"
+        );
+
+        let text = "$ printf 'took 5ms'\n~> took \\d+ms\n! took [..]\n";
+        let script = Script::read(text.as_bytes()).unwrap();
+        let expected = &script.commands[0].expected_outcome;
+        assert!(!expected.std_out.matches("took 5ms"));
+        assert!(expected.std_out.matches("took 5ms\n"));
+        assert!(!expected.std_err.matches("took 5ms"));
+        assert!(expected.std_err.matches("took 5ms\n"));
+        let text = "$ printf 'took 5ms'\n~> took \\d+ms\n\\ No newline at end of file\n\
+                    ! took [..]\n\\ No newline at end of file\n";
+        let script = Script::read(text.as_bytes()).unwrap();
+        let expected = &script.commands[0].expected_outcome;
+        assert!(expected.std_out.matches("took 5ms"));
+        assert!(!expected.std_out.matches("took 5ms\n"));
+        assert!(expected.std_err.matches("took 5ms"));
+        assert!(!expected.std_err.matches("took 5ms\n"));
+
+        let text = "$ ls\n\\ No newline at end of file\n";
+        assert!(Script::read(text.as_bytes()).is_err());
+
+        let text = "$ ls\n\\ a comment\n> a\n\\ another comment\n";
+        let script = Script::read(text.as_bytes()).unwrap();
+        let expected = &script.commands[0].expected_outcome;
+        assert!(expected.std_out.matches("a\n"));
+        assert!(!expected.std_out.matches("a"));
+    }
+
+    #[test]
+    fn read_crlf_to_lf() {
+        let text = "$ ls\n% crlf-to-lf stdout\n> a\n> b\n! c\n";
+        let script = Script::read(text.as_bytes()).unwrap();
+        let expected = &script.commands[0].expected_outcome;
+        assert!(expected.std_out.matches("a\r\nb\r\n"));
+        assert!(expected.std_out.matches("a\nb\n"));
+        assert!(!expected.std_err.matches("c\r\n"));
+    }
+
+    #[test]
+    fn read_exit_status() {
+        let text =
+            "$ a\n? !0\n$ b\n? 1, 3\n$ c\n? 64-78\n$ d\n? SIGTERM\n$ e\n? signal 9\n$ f\n?\n";
+        let script = Script::read(text.as_bytes()).unwrap();
+        let statuses: Vec<&ExpectedStatus> = script
+            .commands
+            .iter()
+            .map(|command| &command.expected_outcome.status)
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                &ExpectedStatus::Failure,
+                &ExpectedStatus::Codes(vec![1..=1, 3..=3]),
+                &ExpectedStatus::Codes(vec![64..=78]),
+                &ExpectedStatus::Signal(libc::SIGTERM),
+                &ExpectedStatus::Signal(9),
+                &ExpectedStatus::Killed,
+            ]
+        );
+        let exited = |e_code| Outcome {
+            e_code: Some(e_code),
+            ..Outcome::default()
+        };
+        let killed = |signal| Outcome {
+            e_code: None,
+            signal: Some(signal),
+            ..Outcome::default()
+        };
+        assert!(statuses[0].matches(&exited(2)) && statuses[0].matches(&killed(9)));
+        assert!(!statuses[0].matches(&exited(0)));
+        assert!(statuses[1].matches(&exited(3)) && !statuses[1].matches(&exited(2)));
+        assert!(statuses[2].matches(&exited(64)) && statuses[2].matches(&exited(78)));
+        assert!(!statuses[2].matches(&exited(79)));
+        assert!(statuses[3].matches(&killed(libc::SIGTERM)) && !statuses[3].matches(&killed(9)));
+        assert!(statuses[5].matches(&killed(9)) && !statuses[5].matches(&exited(0)));
+        assert_eq!(statuses[2].to_string(), "64-78");
+
+        assert!(Script::read("$ a\n? SIGNOSUCH\n".as_bytes()).is_err());
+        assert!(Script::read("$ a\n? 1-\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn file_assertions() {
+        let tmp_dir = tempdir::TempDir::new("file_assertions").unwrap();
+        let dir = tmp_dir.path().to_string_lossy();
+        std::fs::write(tmp_dir.path().join("golden.txt"), "one\n").unwrap();
+        let script_path = tmp_dir.path().join("test.script");
+        let text = format!(
+            "$ mkdir {dir}/sub\n% dir {dir}/sub\n% absent {dir}/x\n\
+             $ sh -c 'echo one >{dir}/x; chmod 640 {dir}/x'\n\
+             % exists {dir}/x\n% mode {dir}/x 640\n% contents {dir}/x <<END\none\nEND\n\
+             % contents {dir}/x @golden.txt\n"
+        );
+        std::fs::write(&script_path, &text).unwrap();
+        let script = Script::read_from(&script_path).unwrap();
+        assert_eq!(script.commands.len(), 2);
+        assert_eq!(script.commands[1].expected_outcome.files.len(), 4);
+        let evaluation = script.evaluate(None).unwrap();
+        assert!(!evaluation.failed(), "{evaluation}");
+
+        let text = format!("$ true\n% contents {dir}/x <<END\ntwo\nEND\n");
+        let script = Script::read(text.as_bytes()).unwrap();
+        match script.evaluate(None).unwrap() {
+            Evaluation::FileFail(_, _, path, difference) => {
+                assert_eq!(path, format!("{dir}/x"));
+                assert_eq!(
+                    difference,
+                    "Expected Contents: two\n\t  Actual Contents: one\n"
+                );
+            }
+            evaluation => panic!("unexpected evaluation: {evaluation}"),
+        }
+        let text = format!("$ true\n% mode {dir}/x 644\n% dir {dir}/sub\n");
+        let script = Script::read(text.as_bytes()).unwrap();
+        assert!(
+            script
+                .evaluate(None)
+                .unwrap()
+                .to_string()
+                .ends_with("has mode 640 not 644")
+        );
+
+        let text = format!(
+            "$ touch '{dir}/my file' {dir}/a.log {dir}/b.log\n\
+             % exists '{dir}/my file'\n% exists {dir}/my\\ file\n% mode {dir}/*.log 644\n"
+        );
+        let script = Script::read(text.as_bytes()).unwrap();
+        let evaluation = script.evaluate(None).unwrap();
+        assert!(!evaluation.failed(), "{evaluation}");
+        let text = format!("$ chmod 600 {dir}/b.log\n% mode {dir}/*.log 644\n");
+        let script = Script::read(text.as_bytes()).unwrap();
+        assert!(
+            script
+                .evaluate(None)
+                .unwrap()
+                .to_string()
+                .ends_with("b.log\" has mode 600 not 644")
+        );
+
+        for directive in ["exists", "dir", "absent"] {
+            let text = format!("$ true\n% {directive} $NO_SUCH_VAR\n");
+            let script = Script::read(text.as_bytes()).unwrap();
+            match script.evaluate(None).unwrap() {
+                Evaluation::FileFail(_, _, path, difference) => {
+                    assert_eq!(
+                        (path.as_str(), difference.as_str()),
+                        ("$NO_SUCH_VAR", "expands to no paths")
+                    );
+                }
+                evaluation => panic!("unexpected evaluation: {evaluation}"),
+            }
+        }
+
+        assert!(Script::read("$ ls\n% mode x rwx\n".as_bytes()).is_err());
+        assert!(Script::read("$ ls\n% exists 'x\n".as_bytes()).is_err());
+        assert!(Script::read("$ ls\n% contents x <<END\none\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn read_std_in() {
+        let text = "$ cat\n<  trimmed\n<| def f():\n<|     return 1\n<|\n";
+        let script = Script::read(text.as_bytes()).unwrap();
+        assert_eq!(
+            script.commands[0].command.std_in.as_deref(),
+            Some("trimmed\ndef f():\n    return 1\n\n")
+        );
+    }
+}